@@ -0,0 +1,37 @@
+import pandas as pd
+import matplotlib.pyplot as plt
+import sys
+import os
+
+# check if the script received a csv file argument
+if len(sys.argv) < 2:
+    print("please provide the csv file name as an argument.")
+    sys.exit(1)
+
+# get the csv file name from the arguments
+csv_file = sys.argv[1]
+
+# read the csv file into a pandas dataframe
+df = pd.read_csv(csv_file)
+
+# validate the file
+required_columns = ['evaluations', 'best_error', 'average_error']
+for col in required_columns:
+    if col not in df.columns:
+        print(f"missing column: {col}")
+        sys.exit(1)
+
+# errors on a log scale, as they shrink by orders of magnitude
+plt.semilogy(df['evaluations'], df['best_error'], label='best error', color='blue')
+plt.semilogy(df['evaluations'], df['average_error'], label='average error', color='cyan')
+
+# adding labels and title
+plt.xlabel('evaluations')
+plt.ylabel('error to optimum')
+plt.title('error to optimum over evaluations')
+
+# save as image with the same name as the problem
+base_name, _ = os.path.splitext(csv_file)   # remove the extension from the file name
+png_filename = f"{base_name}.png"           # add the .png extension
+plt.legend()
+plt.savefig(png_filename)
@@ -15,7 +15,7 @@ csv_file = sys.argv[1]
 df = pd.read_csv(csv_file)
 
 # plotting the data
-plt.plot(df['epoch'], df['average fitness'], label='average fitness', color='cyan')
+plt.plot(df['epoch'], df['average_fitness'], label='average fitness', color='cyan')
 
 # adding labels and title
 plt.xlabel('epoch')
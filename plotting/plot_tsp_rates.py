@@ -0,0 +1,39 @@
+import pandas as pd
+import matplotlib.pyplot as plt
+import sys
+import os
+
+# check if the script received a csv file argument
+if len(sys.argv) < 2:
+    print("please provide the csv file name as an argument.")
+    sys.exit(1)
+
+# get the csv file name from the arguments
+csv_file = sys.argv[1]
+
+# read the csv file into a pandas dataframe
+df = pd.read_csv(csv_file)
+
+# validate the file
+required_columns = ['epoch', 'mean_mutation_rate', 'std_mutation_rate', 'mean_crossover_rate', 'std_crossover_rate']
+for col in required_columns:
+    if col not in df.columns:
+        print(f"missing column: {col}")
+        sys.exit(1)
+
+# plot each mean rate, shaded one standard deviation either side
+for rate, colour in [('mutation', 'red'), ('crossover', 'blue')]:
+    mean = df[f'mean_{rate}_rate']
+    std = df[f'std_{rate}_rate']
+    plt.plot(df['epoch'], mean, label=f'{rate} rate', color=colour)
+    plt.fill_between(df['epoch'], mean - std, mean + std, color=colour, alpha=0.2)
+
+plt.xlabel('epoch')
+plt.ylabel('rate')
+plt.title('self-adaptive rates over epochs')
+plt.legend(loc='upper right')
+
+# save as image with the same name as the csv
+base_name, _ = os.path.splitext(csv_file)   # remove the extension from the file name
+png_filename = f"{base_name}.png"           # add the .png extension
+plt.savefig(png_filename)
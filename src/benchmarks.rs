@@ -0,0 +1,248 @@
+use std::error::Error;
+use rand::Rng;
+
+use genetic_algorithms::gaussian;
+use genetic_algorithms::real::RealFitness;
+
+/// # Continuous Benchmarks
+/// the usual test functions for real-valued optimisers, all minimised, in any number of dimensions
+/// see virtual library of simulation experiments by SURJANOVIC, BINGHAM for definitions and bounds
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Function {
+    Sphere,
+    Rosenbrock,
+    Rastrigin,
+    Ackley,
+    Griewank,
+    Schwefel,
+    Levy,
+    StyblinskiTang
+}
+
+impl Function {
+    pub const ALL: [Function; 8] = [
+        Function::Sphere,
+        Function::Rosenbrock,
+        Function::Rastrigin,
+        Function::Ackley,
+        Function::Griewank,
+        Function::Schwefel,
+        Function::Levy,
+        Function::StyblinskiTang
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Function::Sphere => "sphere",
+            Function::Rosenbrock => "rosenbrock",
+            Function::Rastrigin => "rastrigin",
+            Function::Ackley => "ackley",
+            Function::Griewank => "griewank",
+            Function::Schwefel => "schwefel",
+            Function::Levy => "levy",
+            Function::StyblinskiTang => "styblinski_tang"
+        }
+    }
+
+    /// the usual search domain, the same in every dimension
+    pub fn bounds(&self) -> (f64, f64) {
+        match self {
+            Function::Sphere | Function::Rastrigin => (-5.12, 5.12),
+            Function::Rosenbrock => (-5.0, 10.0),
+            Function::Ackley => (-32.768, 32.768),
+            Function::Griewank => (-600.0, 600.0),
+            Function::Schwefel => (-500.0, 500.0),
+            Function::Levy => (-10.0, 10.0),
+            Function::StyblinskiTang => (-5.0, 5.0)
+        }
+    }
+
+    /// the global minimum is at this value in every dimension
+    fn optimum(&self) -> f64 {
+        match self {
+            Function::Sphere | Function::Rastrigin | Function::Ackley | Function::Griewank => 0.0,
+            Function::Rosenbrock | Function::Levy => 1.0,
+            Function::Schwefel => 420.968_746_3,
+            Function::StyblinskiTang => -2.903_534_027_771_178
+        }
+    }
+
+    /// the known value of the global minimum, which only depends on the dimensions for styblinski-tang
+    fn minimum(&self, dimensions: usize) -> f64 {
+        match self {
+            Function::StyblinskiTang => -39.166_165_703_771_42 * dimensions as f64,
+            _ => 0.0
+        }
+    }
+
+    fn evaluate(&self, z: &[f64]) -> f64 {
+        let d = z.len() as f64;
+        let pi = std::f64::consts::PI;
+
+        match self {
+            Function::Sphere => z.iter().map(|x| x * x).sum(),
+            Function::Rosenbrock => z.windows(2)
+                .map(|pair| 100.0 * (pair[1] - pair[0] * pair[0]).powi(2) + (1.0 - pair[0]).powi(2))
+                .sum(),
+            Function::Rastrigin => 10.0 * d + z.iter()
+                .map(|x| x * x - 10.0 * (2.0 * pi * x).cos())
+                .sum::<f64>(),
+            Function::Ackley => {
+                let squares = z.iter().map(|x| x * x).sum::<f64>() / d;
+                let cosines = z.iter().map(|x| (2.0 * pi * x).cos()).sum::<f64>() / d;
+                -20.0 * (-0.2 * squares.sqrt()).exp() - cosines.exp() + 20.0 + std::f64::consts::E
+            },
+            Function::Griewank => {
+                let sum = z.iter().map(|x| x * x).sum::<f64>() / 4000.0;
+                let product: f64 = z.iter()
+                    .enumerate()
+                    .map(|(i, x)| (x / ((i + 1) as f64).sqrt()).cos())
+                    .product();
+                sum - product + 1.0
+            },
+            Function::Schwefel => {
+                // only a minimum within its bounds, it keeps going down outside them,
+                // which a shift or rotation could otherwise put in reach
+                let (lower, upper) = self.bounds();
+                418.982_887_272_433_8 * d - z.iter()
+                    .map(|x| x.clamp(lower, upper))
+                    .map(|x| x * x.abs().sqrt().sin())
+                    .sum::<f64>()
+            },
+            Function::Levy => {
+                let w: Vec<f64> = z.iter().map(|x| 1.0 + (x - 1.0) / 4.0).collect();
+                let last = w[w.len() - 1];
+                (pi * w[0]).sin().powi(2)
+                    + w[..w.len() - 1].iter()
+                        .map(|wi| (wi - 1.0).powi(2) * (1.0 + 10.0 * (pi * wi + 1.0).sin().powi(2)))
+                        .sum::<f64>()
+                    + (last - 1.0).powi(2) * (1.0 + (2.0 * pi * last).sin().powi(2))
+            },
+            Function::StyblinskiTang => 0.5 * z.iter()
+                .map(|x| x.powi(4) - 16.0 * x * x + 5.0 * x)
+                .sum::<f64>()
+        }
+    }
+}
+
+/// # Benchmark
+/// a test function in a fixed number of dimensions, optionally shifted and rotated,
+/// so an optimiser can't do well just by favouring the centre of the domain or each axis separately
+///
+/// the function is evaluated at `R(x - o) + z*`, where o is where the optimum has been moved to,
+/// R is the rotation, and z* is the function's own optimum
+/// so the optimum is always at o, with the same value, and the error to it is known
+#[derive(Debug, Clone)]
+pub struct Benchmark {
+    function: Function,
+    optimum: Vec<f64>,
+    rotation: Option<Vec<Vec<f64>>>
+}
+
+impl Benchmark {
+    /// unshifted and unrotated, with the optimum where the function has it
+    /// every function needs at least one dimension
+    pub fn new(function: Function, dimensions: usize) -> Result<Self, Box<dyn Error>> {
+        if dimensions == 0 {
+            return Err(format!("{} needs at least one dimension", function.name()).into());
+        }
+
+        Ok(Benchmark {
+            function,
+            optimum: vec![function.optimum(); dimensions],
+            rotation: None
+        })
+    }
+
+    /// move the optimum somewhere random in the middle 80% of the bounds
+    pub fn shifted<R: Rng + ?Sized>(self, rng: &mut R) -> Self {
+        let (lower, upper) = self.function.bounds();
+        let margin = 0.1 * (upper - lower);
+        let optimum = self.optimum.iter()
+            .map(|_| rng.gen_range(lower + margin..=upper - margin))
+            .collect();
+        Benchmark { optimum, ..self }
+    }
+
+    /// rotate about the optimum by a random orthogonal matrix,
+    /// from gram-schmidt on a matrix of standard normal samples
+    pub fn rotated<R: Rng + ?Sized>(self, rng: &mut R) -> Self {
+        let dimensions = self.optimum.len();
+        let mut rotation: Vec<Vec<f64>> = Vec::with_capacity(dimensions);
+
+        while rotation.len() < dimensions {
+            let mut row: Vec<f64> = (0..dimensions).map(|_| gaussian(rng)).collect();
+            for basis in &rotation {
+                let dot: f64 = row.iter().zip(basis).map(|(a, b)| a * b).sum();
+                row.iter_mut().zip(basis).for_each(|(a, b)| *a -= dot * b);
+            }
+
+            // a row that was almost a combination of the others is just drawn again
+            let norm = row.iter().map(|a| a * a).sum::<f64>().sqrt();
+            if norm > 1e-8 {
+                rotation.push(row.into_iter().map(|a| a / norm).collect());
+            }
+        }
+
+        Benchmark { rotation: Some(rotation), ..self }
+    }
+
+    pub fn function(&self) -> Function {
+        self.function
+    }
+
+    pub fn dimensions(&self) -> usize {
+        self.optimum.len()
+    }
+
+    /// where the global minimum is
+    pub fn optimum(&self) -> &[f64] {
+        &self.optimum
+    }
+
+    /// the global minimum, the known value rather than the function evaluated at the optimum,
+    /// which is only as close as the optimum's decimal places
+    pub fn optimum_value(&self) -> f64 {
+        self.function.minimum(self.dimensions())
+    }
+}
+
+impl RealFitness for Benchmark {
+    fn fitness(&self, values: &[f64]) -> f64 {
+        let offset: Vec<f64> = values.iter().zip(&self.optimum).map(|(x, o)| x - o).collect();
+
+        let rotated = match &self.rotation {
+            Some(rotation) => rotation.iter()
+                .map(|row| row.iter().zip(&offset).map(|(r, x)| r * x).sum())
+                .collect(),
+            None => offset
+        };
+
+        let z: Vec<f64> = rotated.iter().map(|x| x + self.function.optimum()).collect();
+        self.function.evaluate(&z)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    #[test]
+    fn optimum_value_matches_the_function_at_its_optimum() {
+        let mut rng = StdRng::seed_from_u64(9);
+        for function in Function::ALL {
+            for dimensions in [1, 2, 10] {
+                let benchmark = Benchmark::new(function, dimensions).unwrap().shifted(&mut rng).rotated(&mut rng);
+                let error = benchmark.fitness(benchmark.optimum()) - benchmark.optimum_value();
+                assert!(error.abs() < 1e-4, "{} in {} dimensions is off by {}", function.name(), dimensions, error);
+            }
+        }
+    }
+
+    #[test]
+    fn needs_a_dimension() {
+        assert!(Function::ALL.iter().all(|function| Benchmark::new(*function, 0).is_err()));
+    }
+}
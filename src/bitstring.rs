@@ -0,0 +1,480 @@
+/// # Bitstrings
+///
+/// bitstring genomes of any length, with the usual operators
+///
+/// - one-point, n-point and uniform crossover
+/// - per-bit mutation, flipping each bit with probability 1/n, or flipping a fixed number of bits
+///
+/// there are two representations behind the same operators, see `Bits`
+/// - `Bitstring`, one bit per byte, easy to read
+/// - `PackedBitstring`, 64 bits per word, for long strings, e.g. onemax with n = 10^5 and above
+///
+/// the operators draw from the rng in the same way for both, so a seeded run gives the same result with either
+use std::collections::hash_map::DefaultHasher;
+use std::error::Error;
+use std::fmt::Debug;
+use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
+use std::ops::Range;
+use rand::Rng;
+
+use crate::{Genotype, Problem, Variation};
+use crate::checkpoint::Checkpoint;
+use crate::discrete::n_point_segments;
+
+/// # Bits
+/// what the operators need from a representation
+pub trait Bits: Genotype + Debug + Send + Sync {
+    /// a string of `len` bits, with bit i set if `bit(i)` is true, called in order
+    fn from_fn<F: FnMut(usize) -> bool>(len: usize, bit: F) -> Self;
+    fn len(&self) -> usize;
+    fn is_empty(&self) -> bool
+        {self.len() == 0}
+    fn get(&self, idx: usize) -> bool;
+    fn flip(&mut self, idx: usize);
+    /// number of bits set
+    fn ones(&self) -> usize;
+    /// swap the bits in `range` with another string of the same length
+    fn swap_range(&mut self, other: &mut Self, range: Range<usize>);
+    /// swap bits `64 * word` to `64 * word + 63` with another string of the same length,
+    /// wherever the matching bit of `mask` is set
+    fn swap_word(&mut self, other: &mut Self, word: usize, mask: u64);
+}
+
+/// the bits as a single checkpoint field, e.g. "0110"
+fn bits_to_field<B: Bits>(bits: &B) -> String {
+    (0..bits.len()).map(|idx| if bits.get(idx) { '1' } else { '0' }).collect()
+}
+
+fn bits_from_fields<B: Bits>(fields: &[&str]) -> Result<B, Box<dyn Error>> {
+    let field = fields.first().ok_or("missing bitstring")?;
+    let bits = field.chars()
+        .map(|bit| match bit {
+            '0' => Ok(false),
+            '1' => Ok(true),
+            _ => Err(format!("invalid bit: {}", bit))
+        })
+        .collect::<Result<Vec<bool>, _>>()?;
+    Ok(B::from_fn(bits.len(), |idx| bits[idx]))
+}
+
+/// one bit per byte
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bitstring(Vec<u8>);
+
+impl Bitstring {
+    /// every element should be 0 or 1
+    pub fn new(bits: Vec<u8>) -> Self {
+        Bitstring(bits)
+    }
+
+    pub fn bits(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl Bits for Bitstring {
+    fn from_fn<F: FnMut(usize) -> bool>(len: usize, mut bit: F) -> Self {
+        Bitstring((0..len).map(|idx| if bit(idx) { 1 } else { 0 }).collect())
+    }
+
+    fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    fn get(&self, idx: usize) -> bool {
+        self.0[idx] == 1
+    }
+
+    fn flip(&mut self, idx: usize) {
+        self.0[idx] ^= 1;
+    }
+
+    fn ones(&self) -> usize {
+        self.0.iter().filter(|bit| **bit == 1).count()
+    }
+
+    fn swap_range(&mut self, other: &mut Self, range: Range<usize>) {
+        self.0[range.clone()].swap_with_slice(&mut other.0[range]);
+    }
+
+    fn swap_word(&mut self, other: &mut Self, word: usize, mask: u64) {
+        let start = 64 * word;
+        let end = (start + 64).min(self.len());
+        for idx in start..end {
+            if mask >> (idx - start) & 1 == 1 {
+                std::mem::swap(&mut self.0[idx], &mut other.0[idx]);
+            }
+        }
+    }
+}
+
+impl Genotype for Bitstring {
+    /// hamming distance, the number of differing bits
+    fn distance(&self, other: &Self) -> f64 {
+        self.0.iter()
+            .zip(other.0.iter())
+            .filter(|(a, b)| **a != **b)
+            .count() as f64
+    }
+
+    /// hash of the bits, as only identical strings are at distance 0
+    fn canonical_hash(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.0.hash(&mut hasher);
+        hasher.finish()
+    }
+}
+
+impl Checkpoint for Bitstring {
+    fn to_fields(&self) -> Vec<String> {
+        vec![bits_to_field(self)]
+    }
+
+    fn from_fields(fields: &[&str]) -> Result<Self, Box<dyn Error>> {
+        bits_from_fields(fields)
+    }
+}
+
+/// 64 bits per word, bit i being bit `i % 64` of word `i / 64`
+/// any bits past the end of the last word are always 0, so whole words can be counted and compared
+#[derive(Debug, Clone, PartialEq)]
+pub struct PackedBitstring {
+    words: Vec<u64>,
+    len: usize
+}
+
+impl PackedBitstring {
+    pub fn words(&self) -> &[u64] {
+        &self.words
+    }
+}
+
+impl Bits for PackedBitstring {
+    fn from_fn<F: FnMut(usize) -> bool>(len: usize, mut bit: F) -> Self {
+        let mut words = vec![0; len.div_ceil(64)];
+        for idx in 0..len {
+            if bit(idx) {
+                words[idx / 64] |= 1 << (idx % 64);
+            }
+        }
+        PackedBitstring { words, len }
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn get(&self, idx: usize) -> bool {
+        self.words[idx / 64] >> (idx % 64) & 1 == 1
+    }
+
+    fn flip(&mut self, idx: usize) {
+        self.words[idx / 64] ^= 1 << (idx % 64);
+    }
+
+    /// popcount of every word
+    fn ones(&self) -> usize {
+        self.words.iter().map(|word| word.count_ones() as usize).sum()
+    }
+
+    /// a word at a time, masking the partial words at either end
+    fn swap_range(&mut self, other: &mut Self, range: Range<usize>) {
+        if range.is_empty() {
+            return;
+        }
+
+        let first = range.start / 64;
+        let last = (range.end - 1) / 64;
+        for word in first..=last {
+            let low = if word == first { range.start % 64 } else { 0 };
+            let high = if word == last { (range.end - 1) % 64 + 1 } else { 64 };
+            let mask = (u64::MAX >> (64 - (high - low))) << low;
+            self.swap_word(other, word, mask);
+        }
+    }
+
+    fn swap_word(&mut self, other: &mut Self, word: usize, mask: u64) {
+        // the bits past the end are 0 in both, so never differ and are never swapped
+        let differ = (self.words[word] ^ other.words[word]) & mask;
+        self.words[word] ^= differ;
+        other.words[word] ^= differ;
+    }
+}
+
+impl Genotype for PackedBitstring {
+    /// hamming distance, the popcount of the differing bits
+    fn distance(&self, other: &Self) -> f64 {
+        self.words.iter()
+            .zip(other.words.iter())
+            .map(|(a, b)| (a ^ b).count_ones())
+            .sum::<u32>() as f64
+    }
+
+    /// hash of the words, as only identical strings are at distance 0
+    fn canonical_hash(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.words.hash(&mut hasher);
+        hasher.finish()
+    }
+}
+
+impl Checkpoint for PackedBitstring {
+    fn to_fields(&self) -> Vec<String> {
+        vec![bits_to_field(self)]
+    }
+
+    fn from_fields(fields: &[&str]) -> Result<Self, Box<dyn Error>> {
+        bits_from_fields(fields)
+    }
+}
+
+/// how two parents are recombined
+#[derive(Debug, Clone, PartialEq)]
+pub enum Crossover {
+    /// swap everything after a random cut point
+    OnePoint,
+    /// cut at `points` distinct random points, and swap every other segment, see `discrete::n_point_crossover`
+    NPoint { points: usize },
+    /// swap each bit with probability 0.5, drawn a word of 64 bits at a time
+    Uniform
+}
+
+/// how a genome is changed, once it's been decided that it will be
+#[derive(Debug, Clone, PartialEq)]
+pub enum Mutation {
+    /// flip each bit with probability 1/n
+    PerBit,
+    /// flip exactly `count` distinct bits
+    Flips { count: usize }
+}
+
+/// # Bitstring Fitness
+/// the only part of a bitstring problem that differs between problems
+/// written against `Bits`, so the same fitness works with either representation
+pub trait BitstringFitness: Sync {
+    fn fitness<B: Bits>(&self, genome: &B) -> f64;
+}
+
+/// # Bitstring Problem
+/// random bitstrings of a fixed `length`, recombined and mutated with the operators chosen,
+/// and evaluated with `fitness`
+/// the representation is `Bitstring` unless another is given, see `PackedBitstringProblem`
+#[derive(Debug, Clone)]
+pub struct BitstringProblem<F: BitstringFitness, B: Bits = Bitstring> {
+    pub length: usize,
+    pub crossover: Crossover,
+    pub mutation: Mutation,
+    /// chance of a genome being mutated at all
+    pub mutation_rate: f64,
+    pub fitness: F,
+    representation: PhantomData<B>
+}
+
+/// a bitstring problem on `PackedBitstring`
+pub type PackedBitstringProblem<F> = BitstringProblem<F, PackedBitstring>;
+
+impl<F: BitstringFitness, B: Bits> BitstringProblem<F, B> {
+    /// one-point crossover, and per-bit mutation on every genome
+    pub fn new(length: usize, fitness: F) -> Self {
+        BitstringProblem {
+            length,
+            crossover: Crossover::OnePoint,
+            mutation: Mutation::PerBit,
+            mutation_rate: 1.0,
+            fitness,
+            representation: PhantomData
+        }
+    }
+}
+
+impl<F: BitstringFitness, B: Bits> Variation for BitstringProblem<F, B> {
+    type Genome = B;
+
+    /// each bit randomly assigned 0 or 1
+    fn random<R: Rng + ?Sized>(&self, rng: &mut R) -> B {
+        B::from_fn(self.length, |_| rng.gen_bool(0.5))
+    }
+
+    fn crossover<R: Rng + ?Sized>(&self, x: &B, y: &B, rng: &mut R) -> (B, B) {
+        let mut child_0 = x.clone();
+        let mut child_1 = y.clone();
+        let len = x.len().min(y.len());
+        if len == 0 {
+            return (child_0, child_1);
+        }
+
+        match self.crossover {
+            Crossover::OnePoint => {
+                // choose swapping index
+                let index = rng.gen_range(0..len);
+                child_0.swap_range(&mut child_1, index..len);
+            },
+            Crossover::NPoint { points } => {
+                for segment in n_point_segments(len, points, rng) {
+                    child_0.swap_range(&mut child_1, segment);
+                }
+            },
+            Crossover::Uniform => {
+                // one random word decides 64 bits at once
+                for word in 0..len.div_ceil(64) {
+                    child_0.swap_word(&mut child_1, word, rng.gen());
+                }
+            }
+        }
+
+        (child_0, child_1)
+    }
+
+    fn mutation<R: Rng + ?Sized>(&self, genome: &B, rng: &mut R) -> B {
+        let mut next = genome.clone();
+        let len = next.len();
+
+        // chance of mutation
+        if len == 0 || rng.gen::<f64>() >= self.mutation_rate {
+            return next;
+        }
+
+        match self.mutation {
+            Mutation::PerBit => {
+                // the gaps between flipped bits are geometric, so skip straight to the next one
+                // instead of drawing for every bit
+                let log_keep = (1.0 - 1.0 / len as f64).ln();
+                let mut idx: usize = 0;
+                loop {
+                    let gap = (rng.gen::<f64>().ln() / log_keep) as usize;
+                    idx = idx.saturating_add(gap);
+                    if idx >= len {
+                        break;
+                    }
+                    next.flip(idx);
+                    idx += 1;
+                }
+            },
+            Mutation::Flips { count } => {
+                // redraw any bit already flipped, few enough are flipped that this is cheap
+                let mut flipped = Vec::with_capacity(count);
+                while flipped.len() < count.min(len) {
+                    let idx = rng.gen_range(0..len);
+                    if !flipped.contains(&idx) {
+                        flipped.push(idx);
+                        next.flip(idx);
+                    }
+                }
+            }
+        }
+
+        next
+    }
+}
+
+impl<F: BitstringFitness, B: Bits> Problem for BitstringProblem<F, B> {
+    fn fitness(&self, genome: &B) -> f64 {
+        self.fitness.fitness(genome)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    #[derive(Debug, Clone)]
+    struct Ones;
+
+    impl BitstringFitness for Ones {
+        fn fitness<B: Bits>(&self, genome: &B) -> f64 {
+            genome.ones() as f64
+        }
+    }
+
+    /// the bits past `len` in the last word
+    fn tail(bits: &PackedBitstring) -> u64 {
+        match bits.len() % 64 {
+            0 => 0,
+            used => bits.words().last().unwrap() >> used
+        }
+    }
+
+    fn same_bits(packed: &PackedBitstring, bytes: &Bitstring) -> bool {
+        packed.len() == bytes.len() && (0..packed.len()).all(|idx| packed.get(idx) == bytes.get(idx))
+    }
+
+    #[test]
+    fn packing_leaves_the_tail_clear() {
+        let bits = PackedBitstring::from_fn(70, |_| true);
+
+        assert_eq!(bits.words(), &[u64::MAX, (1 << 6) - 1]);
+        assert_eq!(bits.ones(), 70);
+        assert_eq!(tail(&bits), 0);
+    }
+
+    #[test]
+    fn swap_range_masks_partial_words() {
+        let lengths = [1, 63, 64, 65, 130];
+        let mut rng = StdRng::seed_from_u64(1);
+
+        for len in lengths {
+            for _ in 0..100 {
+                let start = rng.gen_range(0..len);
+                let end = rng.gen_range(start..=len);
+
+                let x: Vec<bool> = (0..len).map(|_| rng.gen()).collect();
+                let y: Vec<bool> = (0..len).map(|_| rng.gen()).collect();
+                let mut packed_0 = PackedBitstring::from_fn(len, |idx| x[idx]);
+                let mut packed_1 = PackedBitstring::from_fn(len, |idx| y[idx]);
+                let mut bytes_0 = Bitstring::from_fn(len, |idx| x[idx]);
+                let mut bytes_1 = Bitstring::from_fn(len, |idx| y[idx]);
+
+                packed_0.swap_range(&mut packed_1, start..end);
+                bytes_0.swap_range(&mut bytes_1, start..end);
+
+                assert!(same_bits(&packed_0, &bytes_0) && same_bits(&packed_1, &bytes_1), "swapping {}..{} of {}", start, end, len);
+                assert_eq!(tail(&packed_0), 0);
+                assert_eq!(tail(&packed_1), 0);
+            }
+        }
+    }
+
+    #[test]
+    fn representations_agree_under_every_operator() {
+        let crossovers = [Crossover::OnePoint, Crossover::NPoint { points: 3 }, Crossover::Uniform];
+        let mutations = [Mutation::PerBit, Mutation::Flips { count: 5 }];
+
+        for crossover in &crossovers {
+            for mutation in &mutations {
+                let mut bytes: BitstringProblem<Ones> = BitstringProblem::new(130, Ones);
+                bytes.crossover = crossover.clone();
+                bytes.mutation = mutation.clone();
+                let mut packed: PackedBitstringProblem<Ones> = BitstringProblem::new(130, Ones);
+                packed.crossover = crossover.clone();
+                packed.mutation = mutation.clone();
+
+                let mut bytes_rng = StdRng::seed_from_u64(2);
+                let mut packed_rng = StdRng::seed_from_u64(2);
+                let (x, y) = (bytes.random(&mut bytes_rng), bytes.random(&mut bytes_rng));
+                let (a, b) = (packed.random(&mut packed_rng), packed.random(&mut packed_rng));
+
+                let (x, y) = bytes.crossover(&x, &y, &mut bytes_rng);
+                let (a, b) = packed.crossover(&a, &b, &mut packed_rng);
+                let (x, a) = (bytes.mutation(&x, &mut bytes_rng), packed.mutation(&a, &mut packed_rng));
+
+                assert!(same_bits(&a, &x) && same_bits(&b, &y), "{:?} and {:?}", crossover, mutation);
+                assert_eq!(a.ones(), x.ones());
+                assert_eq!(tail(&a), 0);
+                assert_eq!(tail(&b), 0);
+            }
+        }
+    }
+
+    #[test]
+    fn checkpoint_fields_round_trip() {
+        let bits = PackedBitstring::from_fn(70, |idx| idx % 3 == 0);
+        let fields = bits.to_fields();
+        let fields: Vec<&str> = fields.iter().map(|field| field.as_str()).collect();
+
+        assert_eq!(PackedBitstring::from_fields(&fields).unwrap(), bits);
+        assert!(PackedBitstring::from_fields(&["0120"]).is_err());
+    }
+}
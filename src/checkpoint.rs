@@ -0,0 +1,505 @@
+/// # Checkpoints
+///
+/// a long run can be saved every so often and resumed later, continuing where it left off
+/// with the same rng, a resumed run makes the same choices as one that was never stopped,
+/// as long as any state the problem changes over the run, e.g. the weights of an adaptive `operators::OperatorPool`,
+/// is saved by `Problem::save_state`
+/// a checkpoint holds the population, the generation and evaluation counters, the statistics history,
+/// the engine's best-so-far, hall of fame and stagnation counter, the problem's own state, and the state of the rng
+///
+/// checkpoints are csv, one record per line, with the kind of record in the first field
+/// the problem, such as a tsp dataset, isn't saved, only a reference to it,
+/// e.g. the path of the dataset, which it's loaded from when the checkpoint is read
+use std::error::Error;
+use std::fs::{self, File};
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
+use csv::{ReaderBuilder, StringRecord, WriterBuilder};
+use rand::{RngCore, SeedableRng};
+
+use crate::{Generation, Genotype, Individual};
+use crate::constraints::ConstraintHandling;
+use crate::diversity::{Reseed, Restart};
+use crate::hall_of_fame::{Entry, HallOfFame};
+use crate::statistics::Statistics;
+
+const VERSION: &str = "1";
+
+/// # Checkpoint
+/// a genotype that can be written to a checkpoint and read back
+pub trait Checkpoint: Genotype {
+    /// the genome as csv fields
+    fn to_fields(&self) -> Vec<String>;
+    /// rebuild a genome from the fields written by `to_fields`
+    fn from_fields(fields: &[&str]) -> Result<Self, Box<dyn Error>>;
+}
+
+/// # Checkpoint Rng
+/// xoshiro256++, see scrambled linear pseudorandom number generators by BLACKMAN, VIGNA
+///
+/// `StdRng` doesn't expose its state, so a run has to use this rng to be checkpointed
+#[derive(Debug, Clone, PartialEq)]
+pub struct CheckpointRng {
+    state: [u64; 4]
+}
+
+impl CheckpointRng {
+    pub fn state(&self) -> [u64; 4] {
+        self.state
+    }
+
+    /// continue from a state returned by `state`
+    pub fn from_state(state: [u64; 4]) -> Self {
+        CheckpointRng { state }
+    }
+}
+
+impl RngCore for CheckpointRng {
+    fn next_u32(&mut self) -> u32 {
+        // the high bits are the better ones
+        (self.next_u64() >> 32) as u32
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let s = &mut self.state;
+        let result = s[0].wrapping_add(s[3]).rotate_left(23).wrapping_add(s[0]);
+
+        let t = s[1] << 17;
+        s[2] ^= s[0];
+        s[3] ^= s[1];
+        s[1] ^= s[2];
+        s[0] ^= s[3];
+        s[2] ^= t;
+        s[3] = s[3].rotate_left(45);
+
+        result
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        for chunk in dest.chunks_mut(8) {
+            let bytes = self.next_u64().to_le_bytes();
+            chunk.copy_from_slice(&bytes[..chunk.len()]);
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+impl SeedableRng for CheckpointRng {
+    type Seed = [u8; 32];
+
+    fn from_seed(seed: Self::Seed) -> Self {
+        let mut state = [0; 4];
+        for (word, bytes) in state.iter_mut().zip(seed.chunks_exact(8)) {
+            *word = u64::from_le_bytes(bytes.try_into().unwrap());
+        }
+
+        // the all zero state never leaves zero
+        if state == [0; 4] {
+            return CheckpointRng::seed_from_u64(0);
+        }
+        CheckpointRng { state }
+    }
+}
+
+/// where and how often `Engine::run_with_checkpoints` saves
+#[derive(Debug, Clone)]
+pub struct Checkpointing {
+    pub path: PathBuf,
+    /// generations between checkpoints
+    pub interval: usize,
+    /// identifies the problem, e.g. the path of its dataset, given back to `Engine::resume` to load it
+    pub reference: String
+}
+
+/// the parts of an engine's state that aren't in its generation
+pub(crate) struct Progress<T: Genotype> {
+    pub(crate) best: Option<Individual<T>>,
+    pub(crate) best_average: Option<f64>,
+    pub(crate) since_improvement: usize,
+    pub(crate) elapsed: Duration,
+    pub(crate) hall_of_fame: Option<HallOfFame<T>>,
+    /// the records from `Problem::save_state`
+    pub(crate) problem_state: Vec<Vec<String>>
+}
+
+/// write the checkpoint to a temporary file first,
+/// so a crash part way through never leaves a broken checkpoint behind
+pub(crate) fn save<T>(path: &Path, reference: &str, generation: &Generation<T>, progress: &Progress<T>, rng: &CheckpointRng) -> Result<(), Box<dyn Error>>
+where
+    T: Checkpoint + std::fmt::Debug
+{
+    let mut temporary = path.as_os_str().to_owned();
+    temporary.push(".tmp");
+
+    let mut writer = WriterBuilder::new()
+        .flexible(true)
+        .from_writer(BufWriter::new(File::create(&temporary)?));
+
+    writer.write_record(["checkpoint", VERSION])?;
+    writer.write_record(["reference", reference])?;
+
+    let mut record = vec!["rng".to_string()];
+    record.extend(rng.state.iter().map(|word| word.to_string()));
+    writer.write_record(&record)?;
+
+    writer.write_record([
+        "generation".to_string(),
+        generation.generation.to_string(),
+        generation.evaluations.to_string(),
+        generation.population_size.to_string(),
+        generation.average_fitness.to_string(),
+        generation.restarts.to_string(),
+        generation.track_diversity.to_string()
+    ])?;
+
+    let mut record = vec!["constraints".to_string()];
+    record.extend(constraints_to_fields(&generation.constraints));
+    writer.write_record(&record)?;
+
+    let mut record = vec!["duplicates".to_string()];
+    record.extend(generation.duplicates.as_ref().map(reseed_to_fields).unwrap_or_else(|| vec!["keep".to_string()]));
+    writer.write_record(&record)?;
+
+    let mut record = vec!["restart".to_string()];
+    match &generation.restart {
+        Some(restart) => {
+            record.push(restart.min_diversity.to_string());
+            record.push(restart.elites.to_string());
+            record.extend(reseed_to_fields(&restart.reseed));
+        },
+        None => record.push("never".to_string())
+    }
+    writer.write_record(&record)?;
+
+    writer.write_record([
+        "progress".to_string(),
+        progress.best_average.map(|average| average.to_string()).unwrap_or_default(),
+        progress.since_improvement.to_string(),
+        progress.elapsed.as_secs_f64().to_string()
+    ])?;
+
+    for state in &progress.problem_state {
+        let mut record = vec!["problem".to_string()];
+        record.extend(state.iter().cloned());
+        writer.write_record(&record)?;
+    }
+
+    for statistics in &generation.history {
+        let mut record = vec!["statistics".to_string()];
+        record.extend(statistics.to_record());
+        writer.write_record(&record)?;
+    }
+
+    if let Some(best) = &progress.best {
+        writer.write_record(individual_to_fields("best", best))?;
+    }
+    for individual in &generation.population {
+        writer.write_record(individual_to_fields("individual", individual))?;
+    }
+
+    // the generation each entry was found in goes with the capacity, the entries follow in order
+    if let Some(hall_of_fame) = &progress.hall_of_fame {
+        let mut record = vec!["hall_of_fame".to_string(), hall_of_fame.capacity().to_string()];
+        record.extend(hall_of_fame.entries().iter().map(|entry| entry.found().to_string()));
+        writer.write_record(&record)?;
+
+        for entry in hall_of_fame.entries() {
+            writer.write_record(individual_to_fields("fame", entry.individual()))?;
+        }
+    }
+
+    writer.flush()?;
+    drop(writer);
+    fs::rename(&temporary, path)?;
+    Ok(())
+}
+
+/// everything read back from a checkpoint, along with the reference to its problem
+type Restored<T> = (Generation<T>, Progress<T>, CheckpointRng, String);
+
+/// read a checkpoint written by `save`
+pub(crate) fn load<T>(path: &Path) -> Result<Restored<T>, Box<dyn Error>>
+where
+    T: Checkpoint + std::fmt::Debug
+{
+    let mut reader = ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .from_path(path)?;
+
+    let mut generation = Generation::new(0);
+    let mut progress = Progress {
+        best: None,
+        best_average: None,
+        since_improvement: 0,
+        elapsed: Duration::ZERO,
+        hall_of_fame: None,
+        problem_state: Vec::new()
+    };
+    let mut found = Vec::new();
+    let mut rng = None;
+    let mut reference = None;
+
+    for record in reader.records() {
+        let record = record?;
+        match record.get(0).unwrap_or_default() {
+            "checkpoint" => {
+                if record.get(1) != Some(VERSION) {
+                    return Err(format!("unsupported checkpoint version {:?}", record.get(1)).into());
+                }
+            },
+            "reference" => reference = Some(field::<String>(&record, 1)?),
+            "rng" => {
+                rng = Some(CheckpointRng::from_state([
+                    field(&record, 1)?,
+                    field(&record, 2)?,
+                    field(&record, 3)?,
+                    field(&record, 4)?
+                ]));
+            },
+            "generation" => {
+                generation.generation = field(&record, 1)?;
+                generation.evaluations = field(&record, 2)?;
+                generation.population_size = field(&record, 3)?;
+                generation.average_fitness = field(&record, 4)?;
+                generation.restarts = field(&record, 5)?;
+                generation.track_diversity = field(&record, 6)?;
+            },
+            "constraints" => generation.constraints = constraints_from_fields(&record)?,
+            "duplicates" => {
+                generation.duplicates = match record.get(1) {
+                    Some("keep") => None,
+                    _ => Some(reseed_from_fields(&record, 1)?)
+                };
+            },
+            "restart" => {
+                generation.restart = match record.get(1) {
+                    Some("never") => None,
+                    _ => Some(Restart {
+                        min_diversity: field(&record, 1)?,
+                        elites: field(&record, 2)?,
+                        reseed: reseed_from_fields(&record, 3)?
+                    })
+                };
+            },
+            "progress" => {
+                progress.best_average = optional_field(&record, 1)?;
+                progress.since_improvement = field(&record, 2)?;
+                progress.elapsed = Duration::from_secs_f64(field(&record, 3)?);
+            },
+            "problem" => progress.problem_state.push(record.iter().skip(1).map(|field| field.to_string()).collect()),
+            "statistics" => generation.history.push(statistics_from_fields(&record)?),
+            "hall_of_fame" => {
+                progress.hall_of_fame = Some(HallOfFame::new(field(&record, 1)?));
+                found = (2..record.len())
+                    .map(|idx| field(&record, idx))
+                    .collect::<Result<Vec<usize>, _>>()?;
+            },
+            kind @ ("best" | "individual" | "fame") => {
+                let individual = individual_from_fields(&record)?;
+
+                match kind {
+                    "best" => progress.best = Some(individual),
+                    "individual" => generation.population.push(individual),
+                    _ => {
+                        let hall_of_fame = progress.hall_of_fame.as_mut().ok_or("hall of fame entry before the hall of fame")?;
+                        let found = *found.get(hall_of_fame.entries.len()).ok_or("hall of fame has more entries than generations found")?;
+                        hall_of_fame.entries.push(Entry { individual, found });
+                    }
+                }
+            },
+            other => return Err(format!("unknown checkpoint record {:?}", other).into())
+        }
+    }
+
+    generation.statistics = generation.history.last().cloned().unwrap_or_default();
+    let rng = rng.ok_or("checkpoint has no rng state")?;
+    let reference = reference.ok_or("checkpoint has no reference")?;
+    Ok((generation, progress, rng, reference))
+}
+
+/// parse a single field of a record
+fn field<F>(record: &StringRecord, idx: usize) -> Result<F, Box<dyn Error>>
+where
+    F: FromStr,
+    F::Err: Error + 'static
+{
+    let value = record.get(idx).ok_or_else(|| format!("checkpoint record is missing field {}", idx))?;
+    Ok(value.parse()?)
+}
+
+/// parse a single field of a record that may be left empty
+fn optional_field<F>(record: &StringRecord, idx: usize) -> Result<Option<F>, Box<dyn Error>>
+where
+    F: FromStr,
+    F::Err: Error + 'static
+{
+    match record.get(idx) {
+        Some("") | None => Ok(None),
+        Some(_) => Ok(Some(field(record, idx)?))
+    }
+}
+
+/// `[kind, born, objective, fitness, violation count, violations..., genome...]`
+/// the fitness is stored rather than recalculated, as it may have been adjusted for constraints
+fn individual_to_fields<T: Checkpoint>(kind: &str, individual: &Individual<T>) -> Vec<String> {
+    let mut record = vec![
+        kind.to_string(),
+        individual.born.to_string(),
+        individual.objective.to_string(),
+        individual.fitness.to_string(),
+        individual.violations.len().to_string()
+    ];
+    record.extend(individual.violations.iter().map(|v| v.to_string()));
+    record.extend(individual.genome.to_fields());
+    record
+}
+
+fn individual_from_fields<T: Checkpoint>(record: &StringRecord) -> Result<Individual<T>, Box<dyn Error>> {
+    let count: usize = field(record, 4)?;
+    let violations = (0..count)
+        .map(|idx| field(record, 5 + idx))
+        .collect::<Result<Vec<f64>, _>>()?;
+
+    let genome_fields: Vec<&str> = record.iter().skip(5 + count).collect();
+
+    Ok(Individual {
+        genome: T::from_fields(&genome_fields)?,
+        objective: field(record, 2)?,
+        violations,
+        fitness: field(record, 3)?,
+        born: field(record, 1)?
+    })
+}
+
+fn statistics_from_fields(record: &StringRecord) -> Result<Statistics, Box<dyn Error>> {
+    // in the same order as `Statistics::to_record`
+    Ok(Statistics {
+        best: field(record, 1)?,
+        mean: field(record, 2)?,
+        worst: field(record, 3)?,
+        median: field(record, 4)?,
+        std_dev: field(record, 5)?,
+        lower_quartile: field(record, 6)?,
+        upper_quartile: field(record, 7)?,
+        unique: optional_field(record, 8)?,
+        diversity: optional_field(record, 9)?
+    })
+}
+
+fn constraints_to_fields(constraints: &ConstraintHandling) -> Vec<String> {
+    match constraints {
+        ConstraintHandling::None => vec!["none".to_string()],
+        ConstraintHandling::StaticPenalty { weight } => vec!["static_penalty".to_string(), weight.to_string()],
+        ConstraintHandling::DynamicPenalty { c, alpha, beta } => vec!["dynamic_penalty".to_string(), c.to_string(), alpha.to_string(), beta.to_string()],
+        ConstraintHandling::FeasibilityRules => vec!["feasibility_rules".to_string()],
+        ConstraintHandling::Repair => vec!["repair".to_string()]
+    }
+}
+
+fn constraints_from_fields(record: &StringRecord) -> Result<ConstraintHandling, Box<dyn Error>> {
+    Ok(match record.get(1).unwrap_or_default() {
+        "none" => ConstraintHandling::None,
+        "static_penalty" => ConstraintHandling::StaticPenalty { weight: field(record, 2)? },
+        "dynamic_penalty" => ConstraintHandling::DynamicPenalty {
+            c: field(record, 2)?,
+            alpha: field(record, 3)?,
+            beta: field(record, 4)?
+        },
+        "feasibility_rules" => ConstraintHandling::FeasibilityRules,
+        "repair" => ConstraintHandling::Repair,
+        other => return Err(format!("unknown constraint handling {:?}", other).into())
+    })
+}
+
+fn reseed_to_fields(reseed: &Reseed) -> Vec<String> {
+    match reseed {
+        Reseed::Random => vec!["random".to_string()],
+        Reseed::Mutated { mutations } => vec!["mutated".to_string(), mutations.to_string()]
+    }
+}
+
+/// the reseed written by `reseed_to_fields`, starting at field `idx`
+fn reseed_from_fields(record: &StringRecord, idx: usize) -> Result<Reseed, Box<dyn Error>> {
+    Ok(match record.get(idx).unwrap_or_default() {
+        "random" => Reseed::Random,
+        "mutated" => Reseed::Mutated { mutations: field(record, idx + 1)? },
+        other => return Err(format!("unknown reseed {:?}", other).into())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::{Engine, Model, Termination};
+    use crate::bitstring::{Bitstring, BitstringProblem};
+    use crate::test_util::OneMax;
+    use crate::selection::Tournament;
+    use crate::{initialise, FitnessOrder};
+
+    fn engine(generation: Generation<Bitstring>) -> Engine<BitstringProblem<OneMax>, Tournament> {
+        Engine::new(BitstringProblem::new(40, OneMax), generation, FitnessOrder::Max, Tournament::new(3), Model::default(), Termination::default())
+    }
+
+    #[test]
+    fn resumed_run_matches_uninterrupted_run() {
+        let path = std::env::temp_dir().join(format!("checkpoint_round_trip_{}.csv", std::process::id()));
+        let mut rng = CheckpointRng::seed_from_u64(7);
+
+        let mut generation = Generation::new(20);
+        generation.set_constraint_handling(ConstraintHandling::StaticPenalty { weight: 2.0 });
+        initialise(&mut generation, &BitstringProblem::new(40, OneMax), &mut rng);
+        let mut original = engine(generation);
+        original.set_hall_of_fame(5);
+
+        for _ in 0..5 {
+            original.step(&mut rng);
+        }
+        original.save_checkpoint(&path, "one max", &rng).unwrap();
+
+        let (mut resumed, mut resumed_rng) = Engine::resume(&path, FitnessOrder::Max, Tournament::new(3), Model::default(), Termination::default(), |reference| {
+            assert_eq!(reference, "one max");
+            Ok(BitstringProblem::new(40, OneMax))
+        }).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(resumed_rng, rng);
+
+        for _ in 0..5 {
+            original.step(&mut rng);
+            resumed.step(&mut resumed_rng);
+        }
+
+        let (a, b) = (original.generation(), resumed.generation());
+        assert_eq!(a.get_generation(), b.get_generation());
+        assert_eq!(a.get_evaluations(), b.get_evaluations());
+        assert_eq!(a.get_history().len(), b.get_history().len());
+        for (x, y) in a.population.iter().zip(&b.population) {
+            assert_eq!(x.genome(), y.genome());
+            assert_eq!(x.fitness(), y.fitness());
+            assert_eq!(x.born, y.born);
+        }
+        assert_eq!(original.best().unwrap().genome(), resumed.best().unwrap().genome());
+
+        let fame = |engine: &Engine<BitstringProblem<OneMax>, Tournament>| -> Vec<(Bitstring, usize)> {
+            engine.hall_of_fame().unwrap().entries().iter()
+                .map(|entry| (entry.individual().genome().clone(), entry.found()))
+                .collect()
+        };
+        assert_eq!(fame(&original), fame(&resumed));
+    }
+
+    #[test]
+    fn rejects_other_versions() {
+        let path = std::env::temp_dir().join(format!("checkpoint_version_{}.csv", std::process::id()));
+        fs::write(&path, "checkpoint,4\n").unwrap();
+        let loaded = load::<Bitstring>(&path);
+        fs::remove_file(&path).unwrap();
+        assert!(loaded.is_err());
+    }
+}
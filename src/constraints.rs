@@ -0,0 +1,158 @@
+/// # Constraint Handling
+///
+/// problems report constraint violations through `Problem::violations`, separately from their objective
+/// the handling chosen here turns the objective and violations into the fitness used for ranking,
+/// so every selection and replacement scheme respects it without needing to know about constraints
+/// that fitness is only comparable within a generation, anything kept across generations,
+/// like the best so far, uses `FitnessOrder::compare_individuals` instead
+///
+/// - static penalty
+/// - dynamic penalty, see on the use of non-stationary penalty functions by JOINES, HOUCK
+/// - feasibility rules, see an efficient constraint handling method for genetic algorithms by DEB
+/// - repair, offspring are passed through `Problem::repair` before they are evaluated
+use crate::{FitnessOrder, Genotype, Individual};
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum ConstraintHandling {
+    /// violations are ignored, fitness is just the objective
+    #[default]
+    None,
+    /// fitness is the objective, worsened by `weight` times the total violation
+    StaticPenalty { weight: f64 },
+    /// fitness is the objective, worsened by `(c * t)^alpha * sum(violation^beta)`,
+    /// where t is the generation, starting at 1, so the penalty gets harsher as the run goes on
+    DynamicPenalty { c: f64, alpha: f64, beta: f64 },
+    /// feasible solutions always beat infeasible ones, however small the violation,
+    /// feasible solutions are compared by objective,
+    /// and infeasible solutions by total violation
+    FeasibilityRules,
+    /// offspring are repaired before they are evaluated, and fitness is just the objective
+    Repair
+}
+
+impl ConstraintHandling {
+    /// whether the fitness needs to be recalculated from the objective and violations
+    pub(crate) fn adjusts_fitness(&self) -> bool {
+        !matches!(self, ConstraintHandling::None | ConstraintHandling::Repair)
+    }
+
+    /// set the ranking fitness of every individual from its cached objective and violations
+    /// feasibility rules depend on the worst feasible objective, so must see everything being ranked together
+    /// `generation` is the number of epochs run so far
+    pub(crate) fn apply<T: Genotype>(&self, individuals: &mut [&mut Individual<T>], order: &FitnessOrder, generation: usize) {
+        // penalties make the fitness worse, whichever way that is
+        let worsen = |objective: f64, penalty: f64| {
+            if *order == FitnessOrder::Max {
+                objective - penalty
+            } else {
+                objective + penalty
+            }
+        };
+
+        match self {
+            ConstraintHandling::None | ConstraintHandling::Repair => {
+                for individual in individuals.iter_mut() {
+                    individual.fitness = individual.objective;
+                }
+            },
+            ConstraintHandling::StaticPenalty { weight } => {
+                for individual in individuals.iter_mut() {
+                    individual.fitness = worsen(individual.objective, weight * individual.violation());
+                }
+            },
+            ConstraintHandling::DynamicPenalty { c, alpha, beta } => {
+                // t starts at 1, so there's a penalty from the initial population on
+                let severity = (c * (generation + 1) as f64).powf(*alpha);
+                for individual in individuals.iter_mut() {
+                    let violation: f64 = individual.violations.iter().map(|v| v.powf(*beta)).sum();
+                    individual.fitness = worsen(individual.objective, severity * violation);
+                }
+            },
+            ConstraintHandling::FeasibilityRules => {
+                // every infeasible solution is ranked behind the worst feasible one
+                let worst_feasible = individuals.iter()
+                    .filter(|individual| individual.is_feasible())
+                    .map(|individual| individual.objective)
+                    .max_by(|a, b| order.compare(*a, *b))
+                    .unwrap_or(0.0);
+
+                // the gap behind it is at least the size of the worst feasible objective,
+                // so that even a tiny violation can't round off to a tie with it
+                let scale = worst_feasible.abs().max(1.0);
+                for individual in individuals.iter_mut() {
+                    individual.fitness = if individual.is_feasible() {
+                        individual.objective
+                    } else {
+                        worsen(worst_feasible, scale * (1.0 + individual.violation()))
+                    };
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bitstring::Bitstring;
+
+    /// individuals with the given objectives and single violations
+    fn individuals(members: &[(f64, f64)]) -> Vec<Individual<Bitstring>> {
+        members.iter()
+            .map(|(objective, violation)| Individual {
+                genome: Bitstring::new(Vec::new()),
+                objective: *objective,
+                violations: vec![*violation],
+                fitness: 0.0,
+                born: 0
+            })
+            .collect()
+    }
+
+    fn fitness(handling: ConstraintHandling, members: &[(f64, f64)], order: FitnessOrder, generation: usize) -> Vec<f64> {
+        let mut individuals = individuals(members);
+        let mut refs: Vec<&mut Individual<Bitstring>> = individuals.iter_mut().collect();
+        handling.apply(&mut refs, &order, generation);
+        individuals.iter().map(|individual| individual.fitness()).collect()
+    }
+
+    #[test]
+    fn none_and_repair_ignore_violations() {
+        let members = [(5.0, 0.0), (7.0, 2.0)];
+        assert_eq!(fitness(ConstraintHandling::None, &members, FitnessOrder::Max, 3), vec![5.0, 7.0]);
+        assert_eq!(fitness(ConstraintHandling::Repair, &members, FitnessOrder::Min, 3), vec![5.0, 7.0]);
+    }
+
+    #[test]
+    fn static_penalty() {
+        let members = [(5.0, 0.0), (7.0, 2.0)];
+        let handling = ConstraintHandling::StaticPenalty { weight: 3.0 };
+        assert_eq!(fitness(handling.clone(), &members, FitnessOrder::Max, 0), vec![5.0, 1.0]);
+        assert_eq!(fitness(handling, &members, FitnessOrder::Min, 9), vec![5.0, 13.0]);
+    }
+
+    #[test]
+    fn dynamic_penalty_grows_from_the_first_generation() {
+        let members = [(5.0, 0.0), (7.0, 2.0)];
+        let handling = ConstraintHandling::DynamicPenalty { c: 0.5, alpha: 2.0, beta: 2.0 };
+        // (0.5 * 1)^2 * 2^2 = 1, then (0.5 * 4)^2 * 2^2 = 16
+        assert_eq!(fitness(handling.clone(), &members, FitnessOrder::Max, 0), vec![5.0, 6.0]);
+        assert_eq!(fitness(handling, &members, FitnessOrder::Min, 3), vec![5.0, 23.0]);
+    }
+
+    #[test]
+    fn feasibility_rules_put_every_infeasible_behind_every_feasible() {
+        let members = [(1e12, 0.0), (3.0, 0.0), (1e12, 1e-9), (-5.0, 2.0)];
+
+        let max = fitness(ConstraintHandling::FeasibilityRules, &members, FitnessOrder::Max, 0);
+        assert!(max[0] > max[1] && max[1] > max[2] && max[2] > max[3], "{:?}", max);
+
+        let members = [(-1e12, 0.0), (-3.0, 0.0), (-1e12, 1e-9), (5.0, 2.0)];
+        let min = fitness(ConstraintHandling::FeasibilityRules, &members, FitnessOrder::Min, 0);
+        assert!(min[0] < min[1] && min[1] < min[2] && min[2] < min[3], "{:?}", min);
+
+        // with nothing feasible, only the violation counts
+        let none_feasible = fitness(ConstraintHandling::FeasibilityRules, &[(9.0, 1.0), (0.0, 0.5)], FitnessOrder::Max, 0);
+        assert!(none_feasible[1] > none_feasible[0]);
+    }
+}
@@ -0,0 +1,429 @@
+/// # Discrete Vectors
+///
+/// genomes where each gene takes one of a finite set of values, e.g. hyperparameter configurations
+/// - `IntegerVector`, each gene an integer within its own bounds
+/// - `CategoricalVector`, each gene a choice from its own list of options, which have no order
+///
+/// both use n-point or uniform crossover, which are public functions over any vector
+/// integers can be mutated by creep, a small step up or down, or by random reset to any value in bounds
+/// categories only by random reset, as there's no nearby option to creep to
+/// see introduction to evolutionary computing by EIBEN, SMITH
+use std::collections::hash_map::DefaultHasher;
+use std::error::Error;
+use std::fmt::Debug;
+use std::hash::{Hash, Hasher};
+use std::ops::Range;
+use rand::Rng;
+use rand::seq::index;
+
+use crate::{Genotype, Problem, Variation};
+use crate::checkpoint::Checkpoint;
+
+/// how two parents are recombined
+#[derive(Debug, Clone, PartialEq)]
+pub enum Crossover {
+    /// see `n_point_crossover`
+    NPoint { points: usize },
+    /// see `uniform_crossover`
+    Uniform
+}
+
+impl Crossover {
+    fn apply<T: Copy, R: Rng + ?Sized>(&self, x: &[T], y: &[T], rng: &mut R) -> (Vec<T>, Vec<T>) {
+        match self {
+            Crossover::NPoint { points } => n_point_crossover(x, y, *points, rng),
+            Crossover::Uniform => uniform_crossover(x, y, rng)
+        }
+    }
+}
+
+/// how an integer is changed, once it's been decided that it will be
+#[derive(Debug, Clone, PartialEq)]
+pub enum Mutation {
+    /// add or subtract between 1 and `step`, clamped to the gene's bounds
+    Creep { step: i64 },
+    /// replace with any value within the gene's bounds
+    RandomReset
+}
+
+// --------------------
+// Integers
+// --------------------
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct IntegerVector(Vec<i64>);
+
+impl IntegerVector {
+    pub fn new(values: Vec<i64>) -> Self {
+        IntegerVector(values)
+    }
+
+    pub fn values(&self) -> &[i64] {
+        &self.0
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl Genotype for IntegerVector {
+    /// manhattan distance, the total difference between each pair of genes
+    fn distance(&self, other: &Self) -> f64 {
+        self.0.iter()
+            .zip(other.0.iter())
+            .map(|(a, b)| a.abs_diff(*b))
+            .sum::<u64>() as f64
+    }
+
+    /// hash of the values, as only identical vectors are at distance 0
+    fn canonical_hash(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.0.hash(&mut hasher);
+        hasher.finish()
+    }
+}
+
+impl Checkpoint for IntegerVector {
+    /// one field per gene
+    fn to_fields(&self) -> Vec<String> {
+        self.0.iter().map(|value| value.to_string()).collect()
+    }
+
+    fn from_fields(fields: &[&str]) -> Result<Self, Box<dyn Error>> {
+        Ok(IntegerVector(fields.iter().map(|value| value.parse()).collect::<Result<_, _>>()?))
+    }
+}
+
+/// # Integer Fitness
+/// the only part of an integer problem that differs between problems
+pub trait IntegerFitness: Sync {
+    fn fitness(&self, values: &[i64]) -> f64;
+}
+
+/// # Integer Problem
+/// random vectors drawn uniformly from `bounds`, one inclusive `(lower, upper)` per gene,
+/// recombined and mutated with the operators chosen, and evaluated with `fitness`
+#[derive(Debug, Clone)]
+pub struct IntegerProblem<F: IntegerFitness> {
+    pub bounds: Vec<(i64, i64)>,
+    pub crossover: Crossover,
+    pub mutation: Mutation,
+    /// chance of a pair of parents being crossed at all
+    pub crossover_rate: f64,
+    /// chance of each gene being mutated
+    pub mutation_rate: f64,
+    pub fitness: F
+}
+
+impl<F: IntegerFitness> IntegerProblem<F> {
+    /// uniform crossover on 90% of pairs, and creeping one gene per vector by 1 on average
+    pub fn new(bounds: Vec<(i64, i64)>, fitness: F) -> Self {
+        let mutation_rate = 1.0 / bounds.len().max(1) as f64;
+        IntegerProblem {
+            bounds,
+            crossover: Crossover::Uniform,
+            mutation: Mutation::Creep { step: 1 },
+            crossover_rate: 0.9,
+            mutation_rate,
+            fitness
+        }
+    }
+}
+
+impl<F: IntegerFitness> Variation for IntegerProblem<F> {
+    type Genome = IntegerVector;
+
+    fn random<R: Rng + ?Sized>(&self, rng: &mut R) -> IntegerVector {
+        IntegerVector(self.bounds.iter()
+            .map(|(lower, upper)| rng.gen_range(*lower..=*upper))
+            .collect())
+    }
+
+    fn crossover<R: Rng + ?Sized>(&self, x: &IntegerVector, y: &IntegerVector, rng: &mut R) -> (IntegerVector, IntegerVector) {
+        if rng.gen::<f64>() >= self.crossover_rate {
+            return (x.clone(), y.clone());
+        }
+
+        let (child_0, child_1) = self.crossover.apply(&x.0, &y.0, rng);
+        (IntegerVector(child_0), IntegerVector(child_1))
+    }
+
+    fn mutation<R: Rng + ?Sized>(&self, genome: &IntegerVector, rng: &mut R) -> IntegerVector {
+        let mut next = genome.clone();
+
+        for (value, (lower, upper)) in next.0.iter_mut().zip(self.bounds.iter()) {
+            // chance of mutation
+            if rng.gen::<f64>() >= self.mutation_rate {
+                continue;
+            }
+
+            *value = match self.mutation {
+                Mutation::Creep { step } => {
+                    let delta = rng.gen_range(1..=step.max(1));
+                    let creep = if rng.gen_bool(0.5) { -delta } else { delta };
+                    value.saturating_add(creep).clamp(*lower, *upper)
+                },
+                Mutation::RandomReset => rng.gen_range(*lower..=*upper)
+            };
+        }
+
+        next
+    }
+}
+
+impl<F: IntegerFitness> Problem for IntegerProblem<F> {
+    fn fitness(&self, genome: &IntegerVector) -> f64 {
+        self.fitness.fitness(&genome.0)
+    }
+}
+
+// --------------------
+// Categories
+// --------------------
+
+/// the index of the option chosen for each gene
+#[derive(Debug, Clone, PartialEq)]
+pub struct CategoricalVector(Vec<usize>);
+
+impl CategoricalVector {
+    pub fn new(choices: Vec<usize>) -> Self {
+        CategoricalVector(choices)
+    }
+
+    pub fn choices(&self) -> &[usize] {
+        &self.0
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl Genotype for CategoricalVector {
+    /// hamming distance, the number of genes with a different option chosen
+    /// options have no order, so any two different ones are as far apart as any other two
+    fn distance(&self, other: &Self) -> f64 {
+        self.0.iter()
+            .zip(other.0.iter())
+            .filter(|(a, b)| **a != **b)
+            .count() as f64
+    }
+
+    /// hash of the choices, as only identical vectors are at distance 0
+    fn canonical_hash(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.0.hash(&mut hasher);
+        hasher.finish()
+    }
+}
+
+impl Checkpoint for CategoricalVector {
+    /// one field per gene, the index of the option chosen
+    fn to_fields(&self) -> Vec<String> {
+        self.0.iter().map(|choice| choice.to_string()).collect()
+    }
+
+    fn from_fields(fields: &[&str]) -> Result<Self, Box<dyn Error>> {
+        Ok(CategoricalVector(fields.iter().map(|choice| choice.parse()).collect::<Result<_, _>>()?))
+    }
+}
+
+/// # Categorical Fitness
+/// the only part of a categorical problem that differs between problems
+/// given the option chosen for each gene, rather than their indices
+pub trait CategoricalFitness<T>: Sync {
+    fn fitness(&self, choices: &[&T]) -> f64;
+}
+
+/// # Categorical Problem
+/// each gene picks one of its own list of `options`, every gene needing at least one,
+/// recombined with the crossover chosen, mutated by random reset, and evaluated with `fitness`
+#[derive(Debug, Clone)]
+pub struct CategoricalProblem<T: Debug + Sync, F: CategoricalFitness<T>> {
+    pub options: Vec<Vec<T>>,
+    pub crossover: Crossover,
+    /// chance of a pair of parents being crossed at all
+    pub crossover_rate: f64,
+    /// chance of each gene being reset
+    pub mutation_rate: f64,
+    pub fitness: F
+}
+
+impl<T: Debug + Sync, F: CategoricalFitness<T>> CategoricalProblem<T, F> {
+    /// uniform crossover on 90% of pairs, and resetting one gene per vector on average
+    pub fn new(options: Vec<Vec<T>>, fitness: F) -> Self {
+        let mutation_rate = 1.0 / options.len().max(1) as f64;
+        CategoricalProblem {
+            options,
+            crossover: Crossover::Uniform,
+            crossover_rate: 0.9,
+            mutation_rate,
+            fitness
+        }
+    }
+
+    /// the option chosen for each gene
+    pub fn decode(&self, genome: &CategoricalVector) -> Vec<&T> {
+        genome.0.iter()
+            .zip(self.options.iter())
+            .map(|(choice, options)| &options[*choice])
+            .collect()
+    }
+}
+
+impl<T: Debug + Sync, F: CategoricalFitness<T>> Variation for CategoricalProblem<T, F> {
+    type Genome = CategoricalVector;
+
+    fn random<R: Rng + ?Sized>(&self, rng: &mut R) -> CategoricalVector {
+        CategoricalVector(self.options.iter()
+            .map(|options| rng.gen_range(0..options.len()))
+            .collect())
+    }
+
+    fn crossover<R: Rng + ?Sized>(&self, x: &CategoricalVector, y: &CategoricalVector, rng: &mut R) -> (CategoricalVector, CategoricalVector) {
+        if rng.gen::<f64>() >= self.crossover_rate {
+            return (x.clone(), y.clone());
+        }
+
+        let (child_0, child_1) = self.crossover.apply(&x.0, &y.0, rng);
+        (CategoricalVector(child_0), CategoricalVector(child_1))
+    }
+
+    fn mutation<R: Rng + ?Sized>(&self, genome: &CategoricalVector, rng: &mut R) -> CategoricalVector {
+        let mut next = genome.clone();
+
+        for (choice, options) in next.0.iter_mut().zip(self.options.iter()) {
+            // chance of mutation
+            if rng.gen::<f64>() < self.mutation_rate {
+                *choice = rng.gen_range(0..options.len());
+            }
+        }
+
+        next
+    }
+}
+
+impl<T: Debug + Sync, F: CategoricalFitness<T>> Problem for CategoricalProblem<T, F> {
+    fn fitness(&self, genome: &CategoricalVector) -> f64 {
+        self.fitness.fitness(&self.decode(genome))
+    }
+}
+
+// --------------------
+// Crossover Operators
+// --------------------
+
+/// # N-Point Crossover
+/// cut both parents at `points` distinct random points between genes, and swap every other segment
+pub fn n_point_crossover<T: Copy, R: Rng + ?Sized>(x: &[T], y: &[T], points: usize, rng: &mut R) -> (Vec<T>, Vec<T>) {
+    let mut child_0 = x.to_vec();
+    let mut child_1 = y.to_vec();
+
+    for segment in n_point_segments(x.len().min(y.len()), points, rng) {
+        child_0[segment.clone()].swap_with_slice(&mut child_1[segment]);
+    }
+
+    (child_0, child_1)
+}
+
+/// the segments n-point crossover swaps, for genomes that swap them their own way, e.g. packed bitstrings
+/// `points` distinct cuts between genes, so there's never an empty segment, and every other segment after the first
+pub fn n_point_segments<R: Rng + ?Sized>(len: usize, points: usize, rng: &mut R) -> Vec<Range<usize>> {
+    if len == 0 {
+        return Vec::new();
+    }
+
+    let mut cuts: Vec<usize> = index::sample(rng, len - 1, points.min(len - 1))
+        .into_iter()
+        .map(|cut| cut + 1)
+        .collect();
+    cuts.sort_unstable();
+    cuts.push(len);
+
+    // the second segment, the fourth, and so on
+    cuts.windows(2)
+        .step_by(2)
+        .map(|segment| segment[0]..segment[1])
+        .collect()
+}
+
+/// # Uniform Crossover
+/// swap each gene with probability 0.5
+pub fn uniform_crossover<T: Copy, R: Rng + ?Sized>(x: &[T], y: &[T], rng: &mut R) -> (Vec<T>, Vec<T>) {
+    let mut child_0 = x.to_vec();
+    let mut child_1 = y.to_vec();
+
+    for idx in 0..x.len().min(y.len()) {
+        if rng.gen_bool(0.5) {
+            std::mem::swap(&mut child_0[idx], &mut child_1[idx]);
+        }
+    }
+
+    (child_0, child_1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    #[test]
+    fn n_point_segments_alternate_between_distinct_cuts() {
+        let mut rng = StdRng::seed_from_u64(1);
+
+        for _ in 0..1000 {
+            let len = rng.gen_range(1..20);
+            let points = rng.gen_range(0..25);
+            let segments = n_point_segments(len, points, &mut rng);
+
+            // every cut is strictly inside the genome, so no segment is empty, and they're in order
+            assert!(segments.iter().all(|segment| 0 < segment.start && segment.start < segment.end && segment.end <= len));
+            assert!(segments.windows(2).all(|pair| pair[0].end < pair[1].start));
+
+            // with p cuts there are p + 1 segments, and every other one after the first is swapped
+            let cuts = points.min(len - 1);
+            assert_eq!(segments.len(), cuts.div_ceil(2));
+        }
+    }
+
+    #[test]
+    fn n_point_crossover_swaps_whole_segments() {
+        let mut rng = StdRng::seed_from_u64(2);
+        let x = vec![0; 10];
+        let y = vec![1; 10];
+
+        for points in 1..12 {
+            let (child_0, child_1) = n_point_crossover(&x, &y, points, &mut rng);
+
+            // the children are complements, and change parent exactly once per cut
+            assert!(child_0.iter().zip(&child_1).all(|(a, b)| a + b == 1));
+            assert_eq!(child_0[0], 0);
+            let changes = child_0.windows(2).filter(|pair| pair[0] != pair[1]).count();
+            assert_eq!(changes, points.min(9));
+        }
+    }
+
+    #[test]
+    fn uniform_crossover_keeps_every_gene() {
+        let mut rng = StdRng::seed_from_u64(3);
+        let x: Vec<usize> = (0..10).collect();
+        let y: Vec<usize> = (10..20).collect();
+        let (child_0, child_1) = uniform_crossover(&x, &y, &mut rng);
+
+        for idx in 0..10 {
+            let mut genes = [child_0[idx], child_1[idx]];
+            genes.sort_unstable();
+            assert_eq!(genes, [x[idx], y[idx]]);
+        }
+    }
+}
@@ -0,0 +1,258 @@
+/// # Diversity Maintenance
+///
+/// - duplicate elimination, replacing every copy of a genome after the first
+/// - restarts, reseeding everything but the elite once the population has converged
+///
+/// two genomes are duplicates if they are at distance 0,
+/// `Genotype::canonical_hash` is used to avoid comparing every pair
+/// both run at the end of each epoch, before the statistics are taken
+use std::collections::HashMap;
+use rand::Rng;
+
+use crate::{FitnessOrder, Generation, Genotype, Problem};
+use crate::constraints::ConstraintHandling;
+
+/// mutation is given this many tries per change asked for, in case it keeps leaving the genome as it was
+const ATTEMPTS_PER_MUTATION: usize = 100;
+
+/// how replacement genomes are made
+#[derive(Debug, Clone, PartialEq)]
+pub enum Reseed {
+    /// brand new genomes from `Problem::random`
+    Random,
+    /// copies mutated until `mutations` of the mutations have actually changed them
+    Mutated { mutations: usize }
+}
+
+impl Reseed {
+    /// a new genome, based on `source` if mutating
+    fn genome<P: Problem, R: Rng + ?Sized>(&self, problem: &P, source: &P::Genome, rng: &mut R) -> P::Genome {
+        match self {
+            Reseed::Random => problem.random(rng),
+            Reseed::Mutated { mutations } => {
+                let mut genome = source.clone();
+                let mut changes = 0;
+                for _ in 0..mutations * ATTEMPTS_PER_MUTATION {
+                    if changes >= *mutations {
+                        break;
+                    }
+                    let next = problem.mutation(&genome, rng);
+                    if next.distance(&genome) > 0.0 {
+                        changes += 1;
+                    }
+                    genome = next;
+                }
+                genome
+            }
+        }
+    }
+}
+
+/// # Restart
+/// once the mean pairwise distance drops below `min_diversity`,
+/// everything but the best `elites` is replaced
+/// when mutating, each replacement is a mutated copy of one of the elites
+#[derive(Debug, Clone, PartialEq)]
+pub struct Restart {
+    pub min_diversity: f64,
+    pub elites: usize,
+    pub reseed: Reseed
+}
+
+/// replace every genome that is at distance 0 from one earlier in the population
+/// the population is sorted fittest first by the replacement schemes, so the fittest copy is the one kept
+pub(crate) fn eliminate_duplicates<P, R>(gen: &mut Generation<P::Genome>, problem: &P, order: &FitnessOrder, rng: &mut R)
+where
+    P: Problem,
+    R: Rng + ?Sized
+{
+    let Some(reseed) = gen.duplicates.clone() else {
+        return;
+    };
+
+    // genomes with the same hash might be the same, but only distance can say for sure
+    let mut buckets: HashMap<u64, Vec<usize>> = HashMap::new();
+    let mut duplicates = Vec::new();
+    for (idx, individual) in gen.population.iter().enumerate() {
+        let bucket = buckets.entry(individual.genome().canonical_hash()).or_default();
+        if bucket.iter().any(|other| gen.population[*other].genome().distance(individual.genome()) == 0.0) {
+            duplicates.push(idx);
+        } else {
+            bucket.push(idx);
+        }
+    }
+
+    if duplicates.is_empty() {
+        return;
+    }
+
+    // mutated duplicates start from themselves
+    replace(gen, problem, &duplicates, &duplicates, &reseed, order, rng);
+}
+
+/// reseed all but the elite if diversity has collapsed, using the latest statistics
+/// returns whether the population was restarted, in which case the statistics are out of date
+pub(crate) fn restart_if_converged<P, R>(gen: &mut Generation<P::Genome>, problem: &P, order: &FitnessOrder, rng: &mut R) -> bool
+where
+    P: Problem,
+    R: Rng + ?Sized
+{
+    let Some(restart) = gen.restart.clone() else {
+        return false;
+    };
+    // a restart policy always turns on diversity tracking, so this is only missing before the first snapshot
+    let Some(diversity) = gen.statistics.diversity else {
+        return false;
+    };
+    if diversity >= restart.min_diversity {
+        return false;
+    }
+
+    let elites = restart.elites.clamp(1, gen.population.len());
+    gen.population.sort_by(|a, b| order.compare(a.fitness(), b.fitness()));
+
+    let targets: Vec<usize> = (elites..gen.population.len()).collect();
+    let sources: Vec<usize> = targets.iter().map(|_| rng.gen_range(0..elites)).collect();
+    replace(gen, problem, &targets, &sources, &restart.reseed, order, rng);
+
+    gen.restarts += 1;
+    true
+}
+
+/// replace each member in `targets` with a genome reseeded from the matching member in `sources`
+fn replace<P, R>(gen: &mut Generation<P::Genome>, problem: &P, targets: &[usize], sources: &[usize], reseed: &Reseed, order: &FitnessOrder, rng: &mut R)
+where
+    P: Problem,
+    R: Rng + ?Sized
+{
+    let genomes: Vec<P::Genome> = sources.iter()
+        .map(|source| {
+            let genome = reseed.genome(problem, gen.population[*source].genome(), rng);
+            if gen.constraints == ConstraintHandling::Repair {
+                problem.repair(genome, rng)
+            } else {
+                genome
+            }
+        })
+        .collect();
+
+    for (target, individual) in targets.iter().zip(gen.evaluate(problem, genomes)) {
+        gen.population[*target] = individual;
+    }
+    gen.apply_constraints(&mut [], order);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+    use rand::seq::SliceRandom;
+    use crate::Variation;
+    use crate::permutation::{swap_mutation, Permutation};
+
+    /// a round trip, the same whichever city it starts from and whichever way round it goes, like a tsp tour
+    #[derive(Debug, Clone, PartialEq)]
+    struct Tour(Permutation);
+
+    impl Genotype for Tour {
+        fn distance(&self, other: &Self) -> f64 {
+            self.0.edge_distance(&other.0)
+        }
+
+        fn canonical_hash(&self) -> u64 {
+            self.0.cyclic_hash()
+        }
+    }
+
+    /// tours of 10 cities, the fitness depending on where the tour starts, so copies of a tour differ in fitness
+    struct Tours;
+
+    impl Variation for Tours {
+        type Genome = Tour;
+
+        fn random<R: Rng + ?Sized>(&self, rng: &mut R) -> Tour {
+            let mut order: Vec<usize> = (0..10).collect();
+            order.shuffle(rng);
+            Tour(Permutation::new(order))
+        }
+
+        fn crossover<R: Rng + ?Sized>(&self, x: &Tour, y: &Tour, _rng: &mut R) -> (Tour, Tour) {
+            (x.clone(), y.clone())
+        }
+
+        fn mutation<R: Rng + ?Sized>(&self, genome: &Tour, rng: &mut R) -> Tour {
+            let mut order = genome.0.order().to_vec();
+            swap_mutation(&mut order, rng);
+            Tour(Permutation::new(order))
+        }
+    }
+
+    impl Problem for Tours {
+        fn fitness(&self, genome: &Tour) -> f64 {
+            genome.0.order().iter().enumerate().map(|(idx, city)| (idx * city) as f64).sum()
+        }
+    }
+
+    fn tour(order: &[usize]) -> Tour {
+        Tour(Permutation::new(order.to_vec()))
+    }
+
+    #[test]
+    fn duplicates_are_replaced_keeping_the_fittest_copy() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let order = FitnessOrder::Max;
+        let original = tour(&[0, 1, 2, 3, 4, 5, 6, 7, 8, 9]);
+        let rotated = tour(&[5, 6, 7, 8, 9, 0, 1, 2, 3, 4]);
+        let reversed = tour(&[9, 8, 7, 6, 5, 4, 3, 2, 1, 0]);
+        let other = tour(&[0, 2, 1, 3, 4, 5, 6, 7, 8, 9]);
+
+        let mut generation = Generation::new(5);
+        for genome in [original.clone(), rotated, reversed.clone(), other.clone(), reversed] {
+            generation.push(&Tours, genome);
+        }
+        // fittest first, as the replacement schemes leave it
+        generation.population.sort_by(|a, b| order.compare(a.fitness(), b.fitness()));
+        let fittest_copy = generation.population[0].clone();
+        assert_eq!(fittest_copy.genome().distance(&original), 0.0);
+
+        generation.set_duplicate_elimination(Some(Reseed::Random));
+        eliminate_duplicates(&mut generation, &Tours, &order, &mut rng);
+
+        let copies: Vec<_> = generation.population.iter()
+            .filter(|individual| individual.genome().distance(&original) == 0.0)
+            .collect();
+        assert_eq!(copies.len(), 1);
+        assert_eq!(copies[0].genome(), fittest_copy.genome());
+        assert!(generation.population.iter().any(|individual| *individual.genome() == other));
+        // only the three duplicates were evaluated again
+        assert_eq!(generation.get_evaluations(), 5 + 3);
+    }
+
+    #[test]
+    fn restart_keeps_only_the_elites() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let order = FitnessOrder::Max;
+
+        let mut generation = Generation::new(8);
+        crate::initialise(&mut generation, &Tours, &mut rng);
+        let originals: Vec<Tour> = generation.population.iter().map(|individual| individual.genome().clone()).collect();
+
+        let mut ranked = generation.population.clone();
+        ranked.sort_by(|a, b| order.compare(a.fitness(), b.fitness()));
+        let elites: Vec<Tour> = ranked[..2].iter().map(|individual| individual.genome().clone()).collect();
+
+        // never diverse enough, so it always restarts
+        generation.set_restart(Some(Restart { min_diversity: f64::INFINITY, elites: 2, reseed: Reseed::Random }));
+        generation.update_statistics(&order);
+        assert!(restart_if_converged(&mut generation, &Tours, &order, &mut rng));
+
+        let kept: Vec<Tour> = generation.population.iter()
+            .map(|individual| individual.genome().clone())
+            .filter(|genome| originals.contains(genome))
+            .collect();
+        assert_eq!(kept, elites);
+        assert_eq!(generation.population.len(), 8);
+        assert_eq!(generation.get_restarts(), 1);
+    }
+}
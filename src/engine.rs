@@ -0,0 +1,380 @@
+/// # Engine
+///
+/// owns a problem and a generation of its genomes, and runs it until one of the termination criteria is met
+/// replaces the hand-rolled `while` loops with ad hoc stopping rules
+use std::error::Error;
+use std::path::Path;
+use std::time::{Duration, Instant};
+use rand::Rng;
+
+use crate::{epoch, FitnessOrder, Generation, Genotype, Individual, Problem};
+use crate::checkpoint::{self, Checkpoint, CheckpointRng, Checkpointing, Progress};
+use crate::hall_of_fame::HallOfFame;
+use crate::observer::Observer;
+use crate::replacement::Replacement;
+use crate::selection::Selection;
+use crate::steady_state::{steady_state_epoch, SteadyState};
+
+/// how each step of the engine advances the population
+#[derive(Debug, Clone)]
+pub enum Model {
+    /// one call to `epoch` per step
+    Generational(Replacement),
+    /// one call to `steady_state_epoch` per step, i.e. `report_every` evaluations
+    SteadyState(SteadyState)
+}
+
+impl Default for Model {
+    fn default() -> Self {
+        Model::Generational(Replacement::default())
+    }
+}
+
+/// when to stop a run, any criterion left as `None` is never checked
+/// the run stops as soon as any one of the others is met
+#[derive(Debug, Clone, Default)]
+pub struct Termination {
+    pub max_generations: Option<usize>,
+    pub max_evaluations: Option<usize>,
+    pub time_limit: Option<Duration>,
+    /// stop once a feasible individual's objective is at least as good as this
+    pub target_fitness: Option<f64>,
+    /// stop after this many generations without either the best
+    /// or the average fitness improving on its best value so far
+    pub stagnation: Option<usize>,
+    /// stop once the mean pairwise distance in the population drops below this
+    /// turns on diversity tracking, see `Generation::set_diversity_tracking`
+    pub min_diversity: Option<f64>
+}
+
+/// which termination criterion ended the run
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StopReason {
+    MaxGenerations,
+    MaxEvaluations,
+    TimeLimit,
+    TargetFitness,
+    Stagnation,
+    LowDiversity
+}
+
+/// result of a finished run
+#[derive(Debug, Clone)]
+pub struct RunSummary<T: Genotype> {
+    pub reason: StopReason,
+    pub generations: usize,
+    pub evaluations: usize,
+    pub elapsed: Duration,
+    /// best individual seen over the whole run, not just in the final population,
+    /// see `FitnessOrder::compare_individuals`
+    pub best: Individual<T>
+}
+
+#[derive(Debug)]
+pub struct Engine<P: Problem, S: Selection> {
+    problem: P,
+    generation: Generation<P::Genome>,
+    order: FitnessOrder,
+    selection: S,
+    model: Model,
+    termination: Termination,
+    best: Option<Individual<P::Genome>>,
+    best_average: Option<f64>,
+    since_improvement: usize,
+    improved: bool,
+    started: Option<Instant>,
+    hall_of_fame: Option<HallOfFame<P::Genome>>
+}
+
+impl<P, S> Engine<P, S>
+where
+    P: Problem,
+    S: Selection
+{
+    /// the generation should already be initialised, with genomes of the same problem
+    pub fn new(problem: P, mut generation: Generation<P::Genome>, order: FitnessOrder, selection: S, model: Model, termination: Termination) -> Self {
+        if termination.min_diversity.is_some() {
+            generation.set_diversity_tracking(true);
+        }
+
+        // so that observers see the statistics of the initial population
+        generation.update_statistics(&order);
+
+        // the initial population counts as seen, so there's a best before the first step
+        let best = generation.population.iter()
+            .min_by(|a, b| order.compare_individuals(a, b))
+            .cloned();
+        let best_average = Some(generation.get_average_fitness());
+
+        Engine {
+            problem,
+            generation,
+            order,
+            selection,
+            model,
+            termination,
+            best,
+            best_average,
+            since_improvement: 0,
+            improved: false,
+            started: None,
+            hall_of_fame: None
+        }
+    }
+
+    pub fn problem(&self) -> &P {
+        &self.problem
+    }
+
+    pub fn generation(&self) -> &Generation<P::Genome> {
+        &self.generation
+    }
+
+    pub(crate) fn generation_mut(&mut self) -> &mut Generation<P::Genome> {
+        &mut self.generation
+    }
+
+    pub fn order(&self) -> &FitnessOrder {
+        &self.order
+    }
+
+    /// best individual seen so far, across every generation
+    pub fn best(&self) -> Option<&Individual<P::Genome>> {
+        self.best.as_ref()
+    }
+
+    /// keep the best `capacity` distinct individuals seen from now on, starting with the current population
+    /// it's updated after every step, so with a steady state model anything born and replaced within one step is missed
+    pub fn set_hall_of_fame(&mut self, capacity: usize) {
+        let mut hall_of_fame = HallOfFame::new(capacity);
+        hall_of_fame.update(&self.generation.population, &self.order, self.generation.get_generation());
+        self.hall_of_fame = Some(hall_of_fame);
+    }
+
+    pub fn hall_of_fame(&self) -> Option<&HallOfFame<P::Genome>> {
+        self.hall_of_fame.as_ref()
+    }
+
+    /// run a single step, returning the reason to stop if any criterion has been met
+    pub fn step<R: Rng + ?Sized>(&mut self, rng: &mut R) -> Option<StopReason> {
+        self.started.get_or_insert_with(Instant::now);
+
+        match &self.model {
+            Model::Generational(replacement) => {
+                epoch(&mut self.generation, &self.problem, &self.order, &self.selection, replacement, rng)
+            },
+            Model::SteadyState(scheme) => {
+                steady_state_epoch(&mut self.generation, &self.problem, &self.order, &self.selection, scheme, rng)
+            }
+        }
+
+        self.since_improvement += 1;
+        self.improved = false;
+
+        // check if we have a new best solution, or if the average has improved
+        // either of these means we're improving
+        // the best is compared by feasibility and objective, as constraint handling can make fitness differ between generations
+        let current = self.generation.population.iter()
+            .min_by(|a, b| self.order.compare_individuals(a, b))
+            .unwrap();
+        if self.best.as_ref().is_none_or(|best| self.order.compare_individuals(current, best).is_lt()) {
+            self.best = Some(current.clone());
+            self.since_improvement = 0;
+            self.improved = true;
+        }
+
+        let average = self.generation.get_average_fitness();
+        if self.best_average.is_none_or(|best| self.order.compare(average, best).is_lt()) {
+            self.best_average = Some(average);
+            self.since_improvement = 0;
+        }
+
+        if let Some(hall_of_fame) = &mut self.hall_of_fame {
+            hall_of_fame.update(&self.generation.population, &self.order, self.generation.get_generation());
+        }
+
+        self.check_termination()
+    }
+
+    /// keep stepping until a termination criterion is met, calling the observers along the way
+    /// with no criteria set this never returns
+    /// stops early if any observer returns an error
+    pub fn run<R: Rng + ?Sized>(&mut self, rng: &mut R, observers: &mut [&mut dyn Observer<P::Genome>]) -> Result<RunSummary<P::Genome>, Box<dyn Error>> {
+        self.run_with(rng, observers, |_, _| Ok(()))
+    }
+
+    /// `run`, calling `after_step` after every step, once the observers have seen it
+    fn run_with<R, F>(&mut self, rng: &mut R, observers: &mut [&mut dyn Observer<P::Genome>], mut after_step: F) -> Result<RunSummary<P::Genome>, Box<dyn Error>>
+    where
+        R: Rng + ?Sized,
+        F: FnMut(&Self, &R) -> Result<(), Box<dyn Error>>
+    {
+        // redo the initial statistics with diversity if an observer wants it and nothing else did
+        if !self.generation.tracks_diversity() && observers.iter().any(|observer| observer.needs_diversity()) {
+            self.generation.set_diversity_tracking(true);
+            self.generation.update_statistics(&self.order);
+        }
+
+        for observer in observers.iter_mut() {
+            observer.on_start(&self.generation, &self.order)?;
+        }
+
+        loop {
+            let stop = self.step(rng);
+
+            for observer in observers.iter_mut() {
+                observer.on_generation(&self.generation, &self.order)?;
+                if let (true, Some(best)) = (self.improved, &self.best) {
+                    observer.on_improvement(&self.generation, best)?;
+                }
+            }
+
+            after_step(self, rng)?;
+
+            if let Some(reason) = stop {
+                let summary = self.summary(reason);
+                for observer in observers.iter_mut() {
+                    observer.on_termination(&self.generation, &summary)?;
+                }
+                return Ok(summary);
+            }
+        }
+    }
+
+    /// summary of the run so far, panics if the population was empty to begin with
+    pub fn summary(&self, reason: StopReason) -> RunSummary<P::Genome> {
+        RunSummary {
+            reason,
+            generations: self.generation.get_generation(),
+            evaluations: self.generation.get_evaluations(),
+            elapsed: self.started.map(|start| start.elapsed()).unwrap_or_default(),
+            best: self.best.clone().expect("empty population")
+        }
+    }
+
+    /// give up the engine and return the generation it was running
+    pub fn into_generation(self) -> Generation<P::Genome> {
+        self.generation
+    }
+
+    fn check_termination(&self) -> Option<StopReason> {
+        let termination = &self.termination;
+
+        if let (Some(target), Some(best)) = (termination.target_fitness, &self.best) {
+            if best.is_feasible() && self.order.compare(best.objective(), target).is_le() {
+                return Some(StopReason::TargetFitness);
+            }
+        }
+        if termination.max_generations.is_some_and(|max| self.generation.get_generation() >= max) {
+            return Some(StopReason::MaxGenerations);
+        }
+        if termination.max_evaluations.is_some_and(|max| self.generation.get_evaluations() >= max) {
+            return Some(StopReason::MaxEvaluations);
+        }
+        if let (Some(limit), Some(start)) = (termination.time_limit, self.started) {
+            if start.elapsed() >= limit {
+                return Some(StopReason::TimeLimit);
+            }
+        }
+        if termination.stagnation.is_some_and(|max| self.since_improvement >= max) {
+            return Some(StopReason::Stagnation);
+        }
+        if termination.min_diversity.is_some_and(|min| self.generation.get_diversity().is_some_and(|diversity| diversity < min)) {
+            return Some(StopReason::LowDiversity);
+        }
+
+        None
+    }
+}
+
+impl<P, S> Engine<P, S>
+where
+    P: Problem,
+    P::Genome: Checkpoint,
+    S: Selection
+{
+    /// like `run`, but also saves a checkpoint every `checkpointing.interval` generations
+    /// the rng is saved along with everything else, so it has to be a `CheckpointRng`
+    pub fn run_with_checkpoints(&mut self, rng: &mut CheckpointRng, observers: &mut [&mut dyn Observer<P::Genome>], checkpointing: &Checkpointing) -> Result<RunSummary<P::Genome>, Box<dyn Error>> {
+        self.run_with(rng, observers, |engine, rng| {
+            if checkpointing.interval > 0 && engine.generation.get_generation().is_multiple_of(checkpointing.interval) {
+                engine.save_checkpoint(&checkpointing.path, &checkpointing.reference, rng)?;
+            }
+            Ok(())
+        })
+    }
+
+    /// save everything needed to carry on from this point, see `checkpoint`
+    pub fn save_checkpoint(&self, path: &Path, reference: &str, rng: &CheckpointRng) -> Result<(), Box<dyn Error>> {
+        let progress = Progress {
+            best: self.best.clone(),
+            best_average: self.best_average,
+            since_improvement: self.since_improvement,
+            elapsed: self.started.map(|start| start.elapsed()).unwrap_or_default(),
+            hall_of_fame: self.hall_of_fame.clone(),
+            problem_state: self.problem.save_state()
+        };
+        checkpoint::save(path, reference, &self.generation, &progress, rng)
+    }
+
+    /// rebuild an engine, and the rng it was using, from a checkpoint
+    /// `load_problem` is given the reference the checkpoint was saved with, e.g. the path of the dataset
+    /// the problem's settings, the order, selection, model and termination criteria aren't saved, so should match the original run
+    /// anything the problem saved with `Problem::save_state` is given back to it with `Problem::load_state`
+    pub fn resume<F>(path: &Path, order: FitnessOrder, selection: S, model: Model, termination: Termination, load_problem: F) -> Result<(Self, CheckpointRng), Box<dyn Error>>
+    where
+        F: FnOnce(&str) -> Result<P, Box<dyn Error>>
+    {
+        let (mut generation, progress, rng, reference) = checkpoint::load(path)?;
+        if termination.min_diversity.is_some() {
+            generation.set_diversity_tracking(true);
+        }
+
+        let mut problem = load_problem(&reference)?;
+        problem.load_state(&progress.problem_state)?;
+
+        let engine = Engine {
+            problem,
+            generation,
+            order,
+            selection,
+            model,
+            termination,
+            best: progress.best,
+            best_average: progress.best_average,
+            since_improvement: progress.since_improvement,
+            improved: false,
+            // so the time limit counts the time taken before the checkpoint
+            started: Instant::now().checked_sub(progress.elapsed),
+            hall_of_fame: progress.hall_of_fame
+        };
+
+        Ok((engine, rng))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+    use crate::bitstring::{Bitstring, BitstringProblem};
+    use crate::test_util::OneMax;
+    use crate::selection::Tournament;
+    use crate::initialise;
+
+    #[test]
+    fn best_is_seeded_from_the_initial_population() {
+        let mut rng = StdRng::seed_from_u64(5);
+        let problem: BitstringProblem<OneMax, Bitstring> = BitstringProblem::new(30, OneMax);
+        let mut generation = Generation::new(10);
+        initialise(&mut generation, &problem, &mut rng);
+        let fittest = generation.get_best_individual(&FitnessOrder::Max).fitness();
+
+        let engine = Engine::new(problem, generation, FitnessOrder::Max, Tournament::new(2), Model::default(), Termination::default());
+        let summary = engine.summary(StopReason::MaxGenerations);
+
+        assert_eq!(summary.generations, 0);
+        assert_eq!(summary.best.fitness(), fittest);
+    }
+}
@@ -1,102 +1,241 @@
 use csv::Writer;
-use std::{error::Error, process::Command};
-
-use genetic_algorithms::{epoch, initialise, FitnessOrder, Generation};
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use std::{error::Error, fs::File, process::Command};
+
+use genetic_algorithms::{initialise, Engine, FitnessOrder, Generation, Genotype, Model, Problem, RunSummary, Termination};
+use genetic_algorithms::observer::{CsvObserver, Observer};
+use genetic_algorithms::selection::Tournament;
+use genetic_algorithms::niching::Adjusted;
+use genetic_algorithms::replacement::Replacement;
+use genetic_algorithms::bitstring::{BitstringFitness, BitstringProblem, Crossover, Mutation};
+use genetic_algorithms::real::{RealVector, RealVectorProblem};
+use genetic_algorithms::gp::{Tarpeian, Tree, TreeProblem};
 use one_max::OneMax;
 use target_string::TargetString;
 use deceptive_landscape::DeceptiveString;
+use benchmarks::{Benchmark, Function};
+use symbolic_regression::SymbolicRegression;
 
 mod one_max;
 mod target_string;
 mod deceptive_landscape;
+mod benchmarks;
+mod symbolic_regression;
+
+/// seed shared by all three problems, change this to get a different run
+const SEED: u64 = 42;
+
+/// length of every bitstring in the exercises
+const LENGTH: usize = 30;
+
+/// the operators the exercises were set with,
+/// one-point crossover, and a 1% chance of flipping a single bit
+fn exercise<F: BitstringFitness>(fitness: F) -> BitstringProblem<F> {
+    let mut problem = BitstringProblem::new(LENGTH, fitness);
+    problem.crossover = Crossover::OnePoint;
+    problem.mutation = Mutation::Flips { count: 1 };
+    problem.mutation_rate = 0.01;
+    problem
+}
 
-fn main() -> Result<(), Box<dyn Error>> {
-    // python environment
-    let python_path = ".venv/bin/python3";
-    let plotting_script = "plotting/plot_fitness.py";
-    // set up csv writer
-    let mut writer = Writer::from_path("output/one_max.csv").unwrap();
-    writer.write_record(["epoch", "average fitness"])?;
-    let mut idx = 0;
+/// dimensions of every continuous benchmark
+const DIMENSIONS: usize = 10;
 
-    // one max problem
-    let mut one_max_problem: Generation<OneMax> = Generation::new(30);
-    initialise(&mut one_max_problem);
+/// move and rotate the benchmarks' optima, see `Benchmark`
+const SHIFT_AND_ROTATE: bool = false;
 
-    // we know here that the max fitness must be 30
-    while one_max_problem.get_best_fitness() < 30.0 {
-        epoch(&mut one_max_problem, FitnessOrder::Max);
-        writer.write_record([idx.to_string(), one_max_problem.get_average_fitness().to_string()])?;
-        idx += 1;
+/// budget for each continuous benchmark
+const BENCHMARK_EVALUATIONS: usize = 100_000;
+
+/// errors this small count as having found the optimum
+const BENCHMARK_TOLERANCE: f64 = 1e-8;
+
+/// chance of a tree bigger than average being ignored by selection, see `Tarpeian`
+const TARPEIAN_RATE: f64 = 0.3;
+
+/// # Plot Observer
+/// calls a python script to plot the csv once the run has finished
+struct PlotObserver {
+    script: &'static str,
+    csv_path: String
+}
+
+impl<T: Genotype + std::fmt::Debug> Observer<T> for PlotObserver {
+    fn on_termination(&mut self, _generation: &Generation<T>, _summary: &RunSummary<T>) -> Result<(), Box<dyn Error>> {
+        // python environment
+        let python_path = ".venv/bin/python3";
+
+        let output = Command::new(python_path)
+            .arg(self.script)
+            .arg(&self.csv_path)
+            .output()?;
+
+        if !output.status.success() {
+            eprintln!("error: {}", String::from_utf8_lossy(&output.stderr));
+        }
+
+        Ok(())
     }
-    writer.flush()?;
+}
 
-    // plot graph
-    let output = Command::new(python_path)
-        .arg(plotting_script)
-        .arg("output/one_max.csv")
-        .output()?;
+/// # Error Observer
+/// writes how far the best and average fitness are from a known optimum, every generation
+/// `[epoch, evaluations, best_error, average_error]`
+struct ErrorObserver {
+    writer: Writer<File>,
+    optimum: f64
+}
+
+impl ErrorObserver {
+    const HEADERS: [&'static str; 4] = ["epoch", "evaluations", "best_error", "average_error"];
 
-    if !output.status.success() {
-        eprintln!("error: {}", String::from_utf8_lossy(&output.stderr));
+    fn new(csv_path: &str, optimum: f64) -> Result<Self, Box<dyn Error>> {
+        let mut writer = Writer::from_writer(File::create(csv_path)?);
+        writer.write_record(Self::HEADERS)?;
+        Ok(ErrorObserver { writer, optimum })
     }
+}
 
-    println!("--- one max problem ---");
-    println!("best solution:\n{:?}\nfitness: {}", one_max_problem.get_best_solution(), one_max_problem.get_best_fitness());
+impl<T: Genotype + std::fmt::Debug> Observer<T> for ErrorObserver {
+    fn on_generation(&mut self, generation: &Generation<T>, _order: &FitnessOrder) -> Result<(), Box<dyn Error>> {
+        let statistics = generation.get_statistics();
+        self.writer.write_record([
+            generation.get_generation().to_string(),
+            generation.get_evaluations().to_string(),
+            (statistics.best - self.optimum).to_string(),
+            (statistics.mean - self.optimum).to_string()
+        ])?;
+        Ok(())
+    }
 
-    // reset the writer
-    writer = Writer::from_path("output/target_string.csv").unwrap();
-    writer.write_record(["epoch", "average fitness"])?;
-    idx = 0;
+    fn on_termination(&mut self, _generation: &Generation<T>, _summary: &RunSummary<T>) -> Result<(), Box<dyn Error>> {
+        self.writer.flush()?;
+        Ok(())
+    }
+}
 
-    // search for target string
-    let mut target_string: Generation<TargetString> = Generation::new(30);
-    initialise(&mut target_string);
+/// run a problem to completion, logging every generation to `csv_path` and plotting it at the end
+fn solve<P: Problem>(problem: P, csv_path: &str, rng: &mut StdRng) -> Result<RunSummary<P::Genome>, Box<dyn Error>> {
+    let order = FitnessOrder::Max;
+    let selection = Tournament::new(2);
+    let model = Model::Generational(Replacement::Generational { elites: 2 });
 
     // we know here that the max fitness must be 30
-    while target_string.get_best_fitness() < 30.0 {
-        epoch(&mut target_string, FitnessOrder::Max);
-        writer.write_record([idx.to_string(), target_string.get_average_fitness().to_string()])?;
-        idx += 1;
-    }
-    writer.flush()?;
-
-    // plot graph
-    Command::new(python_path)
-        .arg(plotting_script)
-        .arg("output/target_string.csv")
-        .output()?;
-    
+    // for the deceptive string, 30 or more means we've hit either the good solution or the best
+    let termination = Termination {
+        target_fitness: Some(30.0),
+        ..Default::default()
+    };
+
+    // set up csv writer
+    let mut csv = CsvObserver::new(File::create(csv_path)?, &[])?;
+    let mut plot = PlotObserver { script: "plotting/plot_fitness.py", csv_path: csv_path.to_string() };
+
+    let mut generation = Generation::new(30);
+    initialise(&mut generation, &problem, rng);
+
+    let mut engine = Engine::new(problem, generation, order, selection, model, termination);
+    engine.run(rng, &mut [&mut csv, &mut plot])
+}
+
+/// minimise a continuous benchmark until it's within tolerance of the optimum, or the budget runs out
+/// logging the error to `output/<function>.csv` every generation, and plotting it at the end
+fn solve_benchmark(benchmark: Benchmark, rng: &mut StdRng) -> Result<RunSummary<RealVector>, Box<dyn Error>> {
+    let order = FitnessOrder::Min;
+    let selection = Tournament::new(2);
+    let model = Model::Generational(Replacement::Generational { elites: 2 });
+
+    let optimum = benchmark.optimum_value();
+    let termination = Termination {
+        target_fitness: Some(optimum + BENCHMARK_TOLERANCE),
+        max_evaluations: Some(BENCHMARK_EVALUATIONS),
+        ..Default::default()
+    };
+
+    let csv_path = format!("output/{}.csv", benchmark.function().name());
+    let mut errors = ErrorObserver::new(&csv_path, optimum)?;
+    let mut plot = PlotObserver { script: "plotting/plot_error.py", csv_path };
+
+    let problem = RealVectorProblem::uniform(benchmark.dimensions(), benchmark.function().bounds(), benchmark)?;
+    let mut generation = Generation::new(100);
+    initialise(&mut generation, &problem, rng);
+
+    let mut engine = Engine::new(problem, generation, order, selection, model, termination);
+    engine.run(rng, &mut [&mut errors, &mut plot])
+}
+
+/// evolve an expression for koza's quartic, with tarpeian bloat control
+/// logging every generation to `output/symbolic_regression.csv`, the same as the other problems, and plotting it at the end
+fn solve_symbolic_regression(rng: &mut StdRng) -> Result<RunSummary<Tree>, Box<dyn Error>> {
+    let order = FitnessOrder::Min;
+    let selection = Adjusted::new(Tarpeian { rate: TARPEIAN_RATE }, Tournament::new(7));
+    let model = Model::Generational(Replacement::Generational { elites: 2 });
+
+    // close enough at every point to count as the quartic
+    let termination = Termination {
+        target_fitness: Some(0.01),
+        max_generations: Some(50),
+        ..Default::default()
+    };
+
+    let csv_path = "output/symbolic_regression.csv";
+    let mut csv = CsvObserver::new(File::create(csv_path)?, &[])?;
+    let mut plot = PlotObserver { script: "plotting/plot_fitness.py", csv_path: csv_path.to_string() };
+
+    let problem = TreeProblem::new(SymbolicRegression::primitives(), SymbolicRegression::quartic());
+    let mut generation = Generation::new(500);
+    initialise(&mut generation, &problem, rng);
+
+    let mut engine = Engine::new(problem, generation, order, selection, model, termination);
+    engine.run(rng, &mut [&mut csv, &mut plot])
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let mut rng = StdRng::seed_from_u64(SEED);
+
+    // one max problem
+    let one_max = solve(exercise(OneMax), "output/one_max.csv", &mut rng)?;
+
+    println!("--- one max problem ---");
+    println!("best solution:\n{:?}\nfitness: {}", one_max.best.genome(), one_max.best.fitness());
+
+    // search for target string
+    let target_string = solve(exercise(TargetString), "output/target_string.csv", &mut rng)?;
+
     println!("--- target string ---");
     println!("target string: 101011010111010111111101010000");
-    println!("best solution:\n{:?}\nfitness: {}", target_string.get_best_solution(), target_string.get_best_fitness());
-
-    // reset the writer
-    writer = Writer::from_path("output/deceptive_string.csv").unwrap();
-    writer.write_record(["epoch", "average fitness"])?;
-    idx = 0;
+    println!("best solution:\n{:?}\nfitness: {}", target_string.best.genome(), target_string.best.fitness());
 
     // search for target string
-    let mut deceptive_string: Generation<DeceptiveString> = Generation::new(30);
-    initialise(&mut deceptive_string);
-
-    // if we get greater or equal to 30 we've hit either the good solution or the best
-    while deceptive_string.get_best_fitness() < 30.0 {
-        epoch(&mut deceptive_string, FitnessOrder::Max);
-        writer.write_record([idx.to_string(), deceptive_string.get_average_fitness().to_string()])?;
-        idx += 1;
-    }
-    writer.flush()?;
-
-    // plot graph
-    Command::new(python_path)
-        .arg(plotting_script)
-        .arg("output/deceptive_string.csv")
-        .output()?;
-    
+    let deceptive_string = solve(exercise(DeceptiveString), "output/deceptive_string.csv", &mut rng)?;
+
     println!("--- deceptive string ---");
     println!("target string: 101011010111010111111101010000");
-    println!("best solution:\n{:?}\nfitness: {}", deceptive_string.get_best_solution(), deceptive_string.get_best_fitness());
+    println!("best solution:\n{:?}\nfitness: {}", deceptive_string.best.genome(), deceptive_string.best.fitness());
+
+    // continuous benchmarks, reported as the error to their known optima
+    for function in Function::ALL {
+        let mut benchmark = Benchmark::new(function, DIMENSIONS)?;
+        if SHIFT_AND_ROTATE {
+            benchmark = benchmark.shifted(&mut rng).rotated(&mut rng);
+        }
+        let optimum = benchmark.optimum().to_vec();
+        let optimum_value = benchmark.optimum_value();
+
+        let summary = solve_benchmark(benchmark, &mut rng)?;
+
+        println!("--- {} in {} dimensions ---", function.name(), DIMENSIONS);
+        println!("optimum:\n{:?}\nfitness: {}", optimum, optimum_value);
+        println!("best solution:\n{:?}\nerror: {:e} after {} evaluations", summary.best.genome().values(), summary.best.fitness() - optimum_value, summary.evaluations);
+    }
+
+    // genetic programming
+    let regression = solve_symbolic_regression(&mut rng)?;
+
+    println!("--- symbolic regression ---");
+    println!("target: x^4 + x^3 + x^2 + x");
+    println!("best solution:\n{}\nerror: {} with {} nodes after {} generations", regression.best.genome().format(&SymbolicRegression::primitives()), regression.best.fitness(), regression.best.genome().size(), regression.generations);
 
     Ok(())
 }
@@ -0,0 +1,644 @@
+/// # Genetic Programming
+///
+/// expression trees over a user-supplied set of functions and terminals,
+/// see genetic programming: on the programming of computers by means of natural selection by KOZA
+///
+/// - ramped half-and-half initialisation
+/// - subtree crossover, picking function nodes 90% of the time
+/// - subtree, point and hoist mutation
+/// - a depth limit, any child deeper than it is replaced by its parent
+///
+/// trees grow without bound unless something pushes back, so there are two kinds of bloat control,
+/// a selection strategy and an adjustment to put in front of one, both of which use `Genotype::size`
+/// - lexicographic parsimony pressure, see lexicographic parsimony pressure by LUKE, PANAIT
+/// - tarpeian, see covariant tarpeian method for bloat control in genetic programming by POLI
+use std::collections::hash_map::DefaultHasher;
+use std::error::Error;
+use std::fmt::Debug;
+use std::hash::{Hash, Hasher};
+use std::ops::Range;
+use rand::Rng;
+
+use crate::{FitnessOrder, Genotype, Individual, Problem, Variation};
+use crate::checkpoint::Checkpoint;
+use crate::niching::Adjustment;
+use crate::selection::{Selection, Tournament};
+
+/// a function applied to its arguments, which are in the same order as the children of its node
+#[derive(Debug, Clone)]
+pub struct Function<T> {
+    pub name: String,
+    pub arity: usize,
+    pub apply: fn(&[T]) -> T
+}
+
+/// a leaf of a tree
+#[derive(Debug, Clone)]
+pub enum Terminal<T> {
+    /// the input at `index` of those the tree is evaluated with
+    Input { name: String, index: usize },
+    Constant(T)
+}
+
+/// # Primitive Set
+/// everything a tree can be built from
+#[derive(Debug, Clone)]
+pub struct PrimitiveSet<T> {
+    pub functions: Vec<Function<T>>,
+    pub terminals: Vec<Terminal<T>>
+}
+
+impl<T> Default for PrimitiveSet<T> {
+    fn default() -> Self {
+        PrimitiveSet { functions: Vec::new(), terminals: Vec::new() }
+    }
+}
+
+impl<T> PrimitiveSet<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_function(mut self, name: &str, arity: usize, apply: fn(&[T]) -> T) -> Self {
+        self.functions.push(Function { name: name.to_string(), arity, apply });
+        self
+    }
+
+    pub fn with_input(mut self, name: &str, index: usize) -> Self {
+        self.terminals.push(Terminal::Input { name: name.to_string(), index });
+        self
+    }
+
+    pub fn with_constant(mut self, value: T) -> Self {
+        self.terminals.push(Terminal::Constant(value));
+        self
+    }
+}
+
+/// a node of a tree, referring to a primitive by its index in the `PrimitiveSet`
+/// functions keep their arity, so a tree's shape is known without the primitives
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Node {
+    Function { index: usize, arity: usize },
+    Terminal(usize)
+}
+
+impl Node {
+    pub fn arity(&self) -> usize {
+        match self {
+            Node::Function { arity, .. } => *arity,
+            Node::Terminal(_) => 0
+        }
+    }
+}
+
+/// # Tree
+/// nodes in prefix order, each function followed by the subtrees of its arguments
+/// kept flat rather than boxed, so subtrees are just ranges and copying a tree is one allocation
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tree(Vec<Node>);
+
+impl Tree {
+    /// the nodes should form exactly one complete tree
+    pub fn new(nodes: Vec<Node>) -> Self {
+        Tree(nodes)
+    }
+
+    pub fn nodes(&self) -> &[Node] {
+        &self.0
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// number of edges on the longest path from the root, a single terminal being depth 0
+    pub fn depth(&self) -> usize {
+        // children still to come of each function above the current node
+        let mut open: Vec<usize> = Vec::new();
+        let mut depth = 0;
+
+        for node in &self.0 {
+            depth = depth.max(open.len());
+            if node.arity() > 0 {
+                open.push(node.arity());
+                continue;
+            }
+
+            // a leaf completes its parent's argument, and maybe its parent, and so on up
+            while let Some(remaining) = open.last_mut() {
+                *remaining -= 1;
+                if *remaining > 0 {
+                    break;
+                }
+                open.pop();
+            }
+        }
+
+        depth
+    }
+
+    /// nodes of the subtree rooted at `root`
+    pub fn subtree(&self, root: usize) -> Range<usize> {
+        let mut needed = 1;
+        let mut end = root;
+        while needed > 0 {
+            needed = needed + self.0[end].arity() - 1;
+            end += 1;
+        }
+        root..end
+    }
+
+    /// a copy with the nodes in `range` replaced by `subtree`
+    fn replaced(&self, range: Range<usize>, subtree: &[Node]) -> Tree {
+        let mut nodes = Vec::with_capacity(self.len() - range.len() + subtree.len());
+        nodes.extend_from_slice(&self.0[..range.start]);
+        nodes.extend_from_slice(subtree);
+        nodes.extend_from_slice(&self.0[range.end..]);
+        Tree(nodes)
+    }
+
+    /// evaluate the tree on one set of inputs
+    pub fn evaluate<T: Clone>(&self, primitives: &PrimitiveSet<T>, inputs: &[T]) -> T {
+        // going backwards, every argument is on the stack by the time its function is reached
+        let mut stack: Vec<T> = Vec::new();
+
+        for node in self.0.iter().rev() {
+            let value = match node {
+                Node::Terminal(index) => match &primitives.terminals[*index] {
+                    Terminal::Input { index, .. } => inputs[*index].clone(),
+                    Terminal::Constant(value) => value.clone()
+                },
+                Node::Function { index, arity } => {
+                    // the first argument was pushed last
+                    let mut arguments = stack.split_off(stack.len() - arity);
+                    arguments.reverse();
+                    (primitives.functions[*index].apply)(&arguments)
+                }
+            };
+            stack.push(value);
+        }
+
+        stack.pop().expect("empty tree")
+    }
+
+    /// the tree as an s-expression, e.g. `(add x (mul x 2.0))`
+    pub fn format<T: Debug>(&self, primitives: &PrimitiveSet<T>) -> String {
+        let mut formatted = String::new();
+        let mut open: Vec<usize> = Vec::new();
+
+        for node in &self.0 {
+            if open.last().is_some() {
+                formatted.push(' ');
+            }
+
+            match node {
+                Node::Function { index, arity } => {
+                    let name = &primitives.functions[*index].name;
+                    if *arity == 0 {
+                        formatted.push_str(name);
+                    } else {
+                        formatted.push('(');
+                        formatted.push_str(name);
+                        open.push(*arity);
+                        continue;
+                    }
+                },
+                Node::Terminal(index) => match &primitives.terminals[*index] {
+                    Terminal::Input { name, .. } => formatted.push_str(name),
+                    Terminal::Constant(value) => formatted.push_str(&format!("{:?}", value))
+                }
+            }
+
+            // close every function this leaf finishes
+            while let Some(remaining) = open.last_mut() {
+                *remaining -= 1;
+                if *remaining > 0 {
+                    break;
+                }
+                open.pop();
+                formatted.push(')');
+            }
+        }
+
+        formatted
+    }
+}
+
+impl Genotype for Tree {
+    /// number of positions where the prefix orders differ, plus the difference in size
+    /// cheap and 0 only for identical trees, but much cruder than a tree edit distance
+    fn distance(&self, other: &Self) -> f64 {
+        let differ = self.0.iter()
+            .zip(other.0.iter())
+            .filter(|(a, b)| **a != **b)
+            .count();
+        (differ + self.len().abs_diff(other.len())) as f64
+    }
+
+    /// hash of the nodes, as only identical trees are at distance 0
+    fn canonical_hash(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.0.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// number of nodes
+    fn size(&self) -> usize {
+        self.len()
+    }
+}
+
+impl Checkpoint for Tree {
+    /// one field per node in prefix order, `f<index>/<arity>` for functions and `t<index>` for terminals
+    fn to_fields(&self) -> Vec<String> {
+        self.0.iter()
+            .map(|node| match node {
+                Node::Function { index, arity } => format!("f{}/{}", index, arity),
+                Node::Terminal(index) => format!("t{}", index)
+            })
+            .collect()
+    }
+
+    fn from_fields(fields: &[&str]) -> Result<Self, Box<dyn Error>> {
+        let nodes = fields.iter()
+            .map(|field| -> Result<Node, Box<dyn Error>> {
+                if let Some(function) = field.strip_prefix('f') {
+                    let (index, arity) = function.split_once('/').ok_or("function node is missing its arity")?;
+                    Ok(Node::Function { index: index.parse()?, arity: arity.parse()? })
+                } else if let Some(terminal) = field.strip_prefix('t') {
+                    Ok(Node::Terminal(terminal.parse()?))
+                } else {
+                    Err(format!("invalid node: {}", field).into())
+                }
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Tree(nodes))
+    }
+}
+
+/// how a tree is changed, once it's been decided that it will be
+#[derive(Debug, Clone, PartialEq)]
+pub enum Mutation {
+    /// replace a random subtree with a new one grown to at most `max_depth`
+    Subtree { max_depth: usize },
+    /// replace a random node with another primitive of the same arity
+    Point,
+    /// replace the whole tree with one of its own subtrees, which can only shrink it
+    Hoist
+}
+
+/// # Tree Fitness
+/// the only part of a genetic programming problem that differs between problems,
+/// usually evaluating the tree on a set of cases with `Tree::evaluate`
+pub trait TreeFitness<T>: Sync {
+    fn fitness(&self, tree: &Tree, primitives: &PrimitiveSet<T>) -> f64;
+}
+
+/// # Tree Problem
+/// random trees from `primitives` by ramped half-and-half, recombined and mutated with the operators chosen,
+/// and evaluated with `fitness`
+/// needs at least one terminal, and one function to grow anything bigger than a single node
+#[derive(Debug, Clone)]
+pub struct TreeProblem<T: Debug + Sync, F: TreeFitness<T>> {
+    pub primitives: PrimitiveSet<T>,
+    /// each random tree is grown or filled to a depth drawn from this inclusive range
+    pub initial_depth: (usize, usize),
+    /// children deeper than this are replaced by their parents
+    pub max_depth: usize,
+    pub mutation: Mutation,
+    /// chance of a pair of parents being crossed at all
+    pub crossover_rate: f64,
+    /// chance of a tree being mutated at all
+    pub mutation_rate: f64,
+    pub fitness: F
+}
+
+impl<T: Debug + Sync, F: TreeFitness<T>> TreeProblem<T, F> {
+    /// koza's settings, initial depths 2 to 6, a depth limit of 17, and crossing 90% of pairs,
+    /// along with subtree mutation on 10% of trees
+    pub fn new(primitives: PrimitiveSet<T>, fitness: F) -> Self {
+        TreeProblem {
+            primitives,
+            initial_depth: (2, 6),
+            max_depth: 17,
+            mutation: Mutation::Subtree { max_depth: 4 },
+            crossover_rate: 0.9,
+            mutation_rate: 0.1,
+            fitness
+        }
+    }
+
+    /// add a random tree to `nodes`, no deeper than `depth`
+    /// full trees only stop at `depth`, grown trees pick from every primitive so can stop anywhere
+    fn generate<R: Rng + ?Sized>(&self, depth: usize, full: bool, nodes: &mut Vec<Node>, rng: &mut R) {
+        let functions = self.primitives.functions.len();
+        let terminals = self.primitives.terminals.len();
+
+        let pick_function = depth > 0 && functions > 0 && (full || rng.gen_range(0..functions + terminals) < functions);
+        if !pick_function {
+            nodes.push(Node::Terminal(rng.gen_range(0..terminals)));
+            return;
+        }
+
+        let index = rng.gen_range(0..functions);
+        let arity = self.primitives.functions[index].arity;
+        nodes.push(Node::Function { index, arity });
+        for _ in 0..arity {
+            self.generate(depth - 1, full, nodes, rng);
+        }
+    }
+
+    /// root of a subtree to operate on, a function 90% of the time if there are any, as koza did
+    /// otherwise leaves would be picked most of the time, and most crossovers would just swap leaves
+    fn choose_node<R: Rng + ?Sized>(&self, tree: &Tree, rng: &mut R) -> usize {
+        let (functions, leaves): (Vec<usize>, Vec<usize>) = (0..tree.len()).partition(|idx| tree.0[*idx].arity() > 0);
+
+        if !functions.is_empty() && rng.gen::<f64>() < 0.9 {
+            functions[rng.gen_range(0..functions.len())]
+        } else {
+            leaves[rng.gen_range(0..leaves.len())]
+        }
+    }
+
+    /// the parent instead of any child over the depth limit
+    fn limit_depth(&self, child: Tree, parent: &Tree) -> Tree {
+        if child.depth() > self.max_depth {
+            parent.clone()
+        } else {
+            child
+        }
+    }
+}
+
+impl<T: Debug + Sync, F: TreeFitness<T>> Variation for TreeProblem<T, F> {
+    type Genome = Tree;
+
+    /// # Ramped Half-and-Half
+    /// a depth drawn uniformly from `initial_depth`, then grown or filled with equal chance
+    /// over a whole population this gives the usual mix of shapes and sizes
+    fn random<R: Rng + ?Sized>(&self, rng: &mut R) -> Tree {
+        let (lower, upper) = self.initial_depth;
+        let depth = rng.gen_range(lower..=upper.max(lower));
+        let full = rng.gen_bool(0.5);
+
+        let mut nodes = Vec::new();
+        self.generate(depth, full, &mut nodes, rng);
+        Tree(nodes)
+    }
+
+    /// # Subtree Crossover
+    /// swap a random subtree of each parent
+    fn crossover<R: Rng + ?Sized>(&self, x: &Tree, y: &Tree, rng: &mut R) -> (Tree, Tree) {
+        if rng.gen::<f64>() >= self.crossover_rate {
+            return (x.clone(), y.clone());
+        }
+
+        let x_range = x.subtree(self.choose_node(x, rng));
+        let y_range = y.subtree(self.choose_node(y, rng));
+
+        let child_0 = x.replaced(x_range.clone(), &y.0[y_range.clone()]);
+        let child_1 = y.replaced(y_range, &x.0[x_range]);
+
+        (self.limit_depth(child_0, x), self.limit_depth(child_1, y))
+    }
+
+    fn mutation<R: Rng + ?Sized>(&self, genome: &Tree, rng: &mut R) -> Tree {
+        // chance of mutation
+        if rng.gen::<f64>() >= self.mutation_rate {
+            return genome.clone();
+        }
+
+        let root = rng.gen_range(0..genome.len());
+        let child = match self.mutation {
+            Mutation::Subtree { max_depth } => {
+                let mut subtree = Vec::new();
+                self.generate(max_depth, false, &mut subtree, rng);
+                genome.replaced(genome.subtree(root), &subtree)
+            },
+            Mutation::Point => {
+                let mut child = genome.clone();
+                child.0[root] = match genome.0[root] {
+                    Node::Terminal(_) => Node::Terminal(rng.gen_range(0..self.primitives.terminals.len())),
+                    Node::Function { index, arity } => {
+                        // the current function is always a candidate, so there's at least one
+                        let candidates: Vec<usize> = (0..self.primitives.functions.len())
+                            .filter(|idx| self.primitives.functions[*idx].arity == arity)
+                            .collect();
+                        let index = candidates.get(rng.gen_range(0..candidates.len())).copied().unwrap_or(index);
+                        Node::Function { index, arity }
+                    }
+                };
+                child
+            },
+            Mutation::Hoist => Tree(genome.0[genome.subtree(root)].to_vec())
+        };
+
+        self.limit_depth(child, genome)
+    }
+}
+
+impl<T: Debug + Sync, F: TreeFitness<T>> Problem for TreeProblem<T, F> {
+    fn fitness(&self, genome: &Tree) -> f64 {
+        self.fitness.fitness(genome, &self.primitives)
+    }
+}
+
+// --------------------
+// Bloat Control
+// --------------------
+
+/// # Lexicographic Parsimony Pressure
+/// tournament selection, where a tie in fitness goes to the smaller contestant
+/// without the genomes there's nothing to compare sizes of, so `select_by_fitness` is a plain tournament
+/// works best when fitness ties are common, e.g. counting the cases a tree gets right
+#[derive(Debug, Clone)]
+pub struct LexicographicParsimony {
+    pub tournament: Tournament
+}
+
+impl LexicographicParsimony {
+    /// tournaments of `size`, drawn with replacement
+    pub fn new(size: usize) -> Self {
+        LexicographicParsimony { tournament: Tournament::new(size) }
+    }
+}
+
+impl Selection for LexicographicParsimony {
+    fn select_by_fitness<R: Rng + ?Sized>(&self, fitness: &[f64], order: &FitnessOrder, count: usize, rng: &mut R) -> Vec<usize> {
+        self.tournament.select_by_fitness(fitness, order, count, rng)
+    }
+
+    fn select_with_fitness<T: Genotype, R: Rng + ?Sized>(&self, population: &[Individual<T>], fitness: &[f64], order: &FitnessOrder, count: usize, rng: &mut R) -> Vec<usize> {
+        self.tournament.hold(population.len(), count, rng, |a, b| {
+            order.compare(fitness[*a], fitness[*b])
+                .then_with(|| population[*a].genome().size().cmp(&population[*b].genome().size()))
+        })
+    }
+}
+
+/// # Tarpeian
+/// each member bigger than the mean size has its fitness set to that of the least fit member, with probability `rate`
+/// so growing is only worth it for a tree that's fitter by enough to make up for sometimes being ignored
+/// an adjustment, so it goes in front of a selection strategy with `niching::Adjusted`
+///
+/// poli did this before evaluating, to skip evaluating the trees it hit,
+/// here every child has already been evaluated, so it only affects selection
+#[derive(Debug, Clone)]
+pub struct Tarpeian {
+    pub rate: f64
+}
+
+impl Adjustment for Tarpeian {
+    fn adjust<T: Genotype, R: Rng + ?Sized>(&self, population: &[Individual<T>], order: &FitnessOrder, fitness: &mut [f64], rng: &mut R) {
+        let worst = fitness.iter().copied().max_by(|a, b| order.compare(*a, *b)).unwrap_or(0.0);
+        let mean_size = population.iter().map(|individual| individual.genome().size()).sum::<usize>() as f64 / population.len().max(1) as f64;
+
+        for (value, individual) in fitness.iter_mut().zip(population) {
+            if individual.genome().size() as f64 > mean_size && rng.gen::<f64>() < self.rate {
+                *value = worst;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::niching::Adjusted;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    struct Size;
+
+    impl TreeFitness<f64> for Size {
+        fn fitness(&self, tree: &Tree, _primitives: &PrimitiveSet<f64>) -> f64 {
+            tree.len() as f64
+        }
+    }
+
+    fn primitives() -> PrimitiveSet<f64> {
+        PrimitiveSet::<f64>::new()
+            .with_function("add", 2, |args| args[0] + args[1])
+            .with_function("sub", 2, |args| args[0] - args[1])
+            .with_function("neg", 1, |args| -args[0])
+            .with_function("if", 3, |args| if args[0] > 0.0 { args[1] } else { args[2] })
+            .with_input("x", 0)
+            .with_constant(2.0)
+    }
+
+    /// whether the nodes form exactly one complete tree
+    fn complete(nodes: &[Node]) -> bool {
+        let mut needed = 1;
+        for (idx, node) in nodes.iter().enumerate() {
+            needed = needed + node.arity() - 1;
+            if needed == 0 {
+                return idx == nodes.len() - 1;
+            }
+        }
+        false
+    }
+
+    fn problem() -> TreeProblem<f64, Size> {
+        TreeProblem::new(primitives(), Size)
+    }
+
+    #[test]
+    fn evaluates_arguments_in_order() {
+        // (sub (add x 2) (neg x)), i.e. (x + 2) - (-x)
+        let tree = Tree::new(vec![
+            Node::Function { index: 1, arity: 2 },
+            Node::Function { index: 0, arity: 2 },
+            Node::Terminal(0),
+            Node::Terminal(1),
+            Node::Function { index: 2, arity: 1 },
+            Node::Terminal(0)
+        ]);
+
+        assert_eq!(tree.evaluate(&primitives(), &[3.0]), 8.0);
+        assert_eq!(tree.format(&primitives()), "(sub (add x 2.0) (neg x))");
+        assert_eq!(tree.depth(), 2);
+        assert_eq!(tree.subtree(1), 1..4);
+        assert_eq!(tree.subtree(4), 4..6);
+        assert_eq!(Tree::new(vec![Node::Terminal(1)]).evaluate(&primitives(), &[3.0]), 2.0);
+    }
+
+    #[test]
+    fn every_subtree_is_a_complete_tree() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let problem = problem();
+
+        for _ in 0..100 {
+            let tree = problem.random(&mut rng);
+            assert_eq!(tree.subtree(0), 0..tree.len());
+            for root in 0..tree.len() {
+                let range = tree.subtree(root);
+                assert!(range.end <= tree.len());
+                assert!(complete(&tree.nodes()[range]));
+            }
+        }
+    }
+
+    #[test]
+    fn children_respect_the_depth_limit() {
+        let mut rng = StdRng::seed_from_u64(1);
+        for mutation in [Mutation::Subtree { max_depth: 4 }, Mutation::Point, Mutation::Hoist] {
+            let problem = TreeProblem {
+                max_depth: 5,
+                initial_depth: (2, 5),
+                mutation,
+                crossover_rate: 1.0,
+                mutation_rate: 1.0,
+                ..problem()
+            };
+
+            for _ in 0..200 {
+                let (x, y) = (problem.random(&mut rng), problem.random(&mut rng));
+                let (child_0, child_1) = problem.crossover(&x, &y, &mut rng);
+                for child in [child_0, child_1] {
+                    let child = problem.mutation(&child, &mut rng);
+                    assert!(child.depth() <= 5, "depth {} over the limit", child.depth());
+                    assert!(complete(child.nodes()));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn checkpoint_fields_round_trip() {
+        let mut rng = StdRng::seed_from_u64(2);
+        let tree = problem().random(&mut rng);
+        let fields = tree.to_fields();
+        let fields: Vec<&str> = fields.iter().map(|field| field.as_str()).collect();
+        assert_eq!(Tree::from_fields(&fields).unwrap(), tree);
+    }
+
+    #[test]
+    fn bloat_control() {
+        let mut rng = StdRng::seed_from_u64(3);
+        let problem = problem();
+        // (add x 2), and x twice, with fitness 3, 1 and 1
+        let big = Tree::new(vec![Node::Function { index: 0, arity: 2 }, Node::Terminal(0), Node::Terminal(1)]);
+        let small = Tree::new(vec![Node::Terminal(0)]);
+        let population: Vec<Individual<Tree>> = [big, small.clone(), small].into_iter()
+            .map(|tree| Individual::new(&problem, tree))
+            .collect();
+
+        // every tournament has all three contestants, the biggest being the fittest
+        let parsimony = LexicographicParsimony { tournament: Tournament::without_replacement(3) };
+        assert_eq!(parsimony.select(&population, &FitnessOrder::Max, 10, &mut rng), vec![0; 10]);
+        assert!(parsimony.select(&population[..0], &FitnessOrder::Max, 10, &mut rng).is_empty());
+
+        // with the biggest always hit, every contestant ties, and the smaller ones win
+        let mut fitness = vec![3.0, 1.0, 1.0];
+        Tarpeian { rate: 1.0 }.adjust(&population, &FitnessOrder::Max, &mut fitness, &mut rng);
+        assert_eq!(fitness, vec![1.0, 1.0, 1.0]);
+
+        let selection = Adjusted::new(Tarpeian { rate: 1.0 }, parsimony);
+        assert!(selection.select(&population, &FitnessOrder::Max, 10, &mut rng).iter().all(|idx| *idx != 0));
+    }
+}
@@ -0,0 +1,183 @@
+/// # Hall of Fame
+///
+/// the best `capacity` distinct individuals seen over a whole run, not just in the final population,
+/// along with the generation each was first found in
+/// useful for looking at near-optimal alternatives to the single best solution
+///
+/// two individuals are the same if their genomes are at distance 0, and only the first one found is kept
+/// individuals from different generations are compared with `FitnessOrder::compare_individuals`,
+/// as the fitness can depend on the generation they were ranked in
+use std::error::Error;
+use std::io::Write;
+use csv::WriterBuilder;
+
+use crate::{FitnessOrder, Genotype, Individual};
+use crate::checkpoint::Checkpoint;
+
+/// a member of the hall of fame
+#[derive(Debug, Clone)]
+pub struct Entry<T: Genotype> {
+    pub(crate) individual: Individual<T>,
+    pub(crate) found: usize
+}
+
+impl<T: Genotype> Entry<T> {
+    pub fn individual(&self) -> &Individual<T> {
+        &self.individual
+    }
+
+    pub fn fitness(&self) -> f64 {
+        self.individual.fitness()
+    }
+
+    /// generation the individual was first seen in
+    pub fn found(&self) -> usize {
+        self.found
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct HallOfFame<T: Genotype> {
+    capacity: usize,
+    /// best first
+    pub(crate) entries: Vec<Entry<T>>
+}
+
+impl<T: Genotype> HallOfFame<T> {
+    pub fn new(capacity: usize) -> Self {
+        HallOfFame {
+            capacity,
+            entries: Vec::with_capacity(capacity)
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// best first
+    pub fn entries(&self) -> &[Entry<T>] {
+        &self.entries
+    }
+
+    pub fn best(&self) -> Option<&Entry<T>> {
+        self.entries.first()
+    }
+
+    /// consider every member of a population, found in `generation`
+    pub fn update(&mut self, population: &[Individual<T>], order: &FitnessOrder, generation: usize) {
+        for individual in population {
+            self.insert(individual, generation, order);
+        }
+    }
+
+    /// consider every entry of another hall of fame, keeping the generations they were found in,
+    /// e.g. to collect the best of several runs
+    pub fn merge(&mut self, other: &HallOfFame<T>, order: &FitnessOrder) {
+        for entry in &other.entries {
+            self.insert(&entry.individual, entry.found, order);
+        }
+    }
+
+    fn insert(&mut self, individual: &Individual<T>, found: usize, order: &FitnessOrder) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        // cheap check first, most of a population won't make it in
+        if self.entries.len() >= self.capacity && !self.entries.last().is_some_and(|worst| order.compare_individuals(individual, &worst.individual).is_lt()) {
+            return;
+        }
+        if self.entries.iter().any(|entry| entry.individual.genome().distance(individual.genome()) == 0.0) {
+            return;
+        }
+
+        // after any entries just as good, so ties go to whichever was found first
+        let idx = self.entries.partition_point(|entry| order.compare_individuals(&entry.individual, individual).is_le());
+        self.entries.insert(idx, Entry { individual: individual.clone(), found });
+        self.entries.truncate(self.capacity);
+    }
+}
+
+impl<T: Checkpoint> HallOfFame<T> {
+    /// one row per entry, best first, `[rank, fitness, objective, found, genome...]`
+    /// the genome is written with `Checkpoint::to_fields`, so takes as many fields as it needs
+    pub fn write<W: Write>(&self, inner: W) -> Result<(), Box<dyn Error>> {
+        let mut writer = WriterBuilder::new()
+            .flexible(true)
+            .from_writer(inner);
+
+        writer.write_record(["rank", "fitness", "objective", "found", "genome"])?;
+
+        for (rank, entry) in self.entries.iter().enumerate() {
+            let mut record = vec![
+                rank.to_string(),
+                entry.fitness().to_string(),
+                entry.individual.objective().to_string(),
+                entry.found.to_string()
+            ];
+            record.extend(entry.individual.genome().to_fields());
+            writer.write_record(&record)?;
+        }
+
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bitstring::{Bitstring, BitstringProblem};
+    use crate::test_util::OneMax;
+
+    fn individual(bits: &[u8]) -> Individual<Bitstring> {
+        let problem: BitstringProblem<OneMax, Bitstring> = BitstringProblem::new(bits.len(), OneMax);
+        Individual::new(&problem, Bitstring::new(bits.to_vec()))
+    }
+
+    fn genomes(hall_of_fame: &HallOfFame<Bitstring>) -> Vec<(Vec<u8>, usize)> {
+        hall_of_fame.entries().iter().map(|entry| (entry.individual().genome().bits().to_vec(), entry.found())).collect()
+    }
+
+    #[test]
+    fn keeps_only_the_best() {
+        let mut hall_of_fame = HallOfFame::new(2);
+        hall_of_fame.update(&[individual(&[1, 0, 0]), individual(&[1, 1, 1]), individual(&[0, 0, 0]), individual(&[1, 1, 0])], &FitnessOrder::Max, 0);
+        assert_eq!(genomes(&hall_of_fame), vec![(vec![1, 1, 1], 0), (vec![1, 1, 0], 0)]);
+
+        let mut empty = HallOfFame::new(0);
+        empty.update(&[individual(&[1, 1, 1])], &FitnessOrder::Max, 0);
+        assert!(empty.best().is_none());
+    }
+
+    #[test]
+    fn rejects_duplicates() {
+        let mut hall_of_fame = HallOfFame::new(5);
+        hall_of_fame.update(&[individual(&[1, 1, 0]), individual(&[1, 1, 0])], &FitnessOrder::Max, 0);
+        hall_of_fame.update(&[individual(&[1, 1, 0])], &FitnessOrder::Max, 1);
+        assert_eq!(genomes(&hall_of_fame), vec![(vec![1, 1, 0], 0)]);
+    }
+
+    #[test]
+    fn ties_go_to_the_first_found() {
+        let mut hall_of_fame = HallOfFame::new(2);
+        hall_of_fame.update(&[individual(&[1, 1, 0])], &FitnessOrder::Max, 0);
+        hall_of_fame.update(&[individual(&[0, 1, 1]), individual(&[1, 0, 1])], &FitnessOrder::Max, 1);
+        // the later ties go behind, and the last one doesn't make it in
+        assert_eq!(genomes(&hall_of_fame), vec![(vec![1, 1, 0], 0), (vec![0, 1, 1], 1)]);
+    }
+
+    #[test]
+    fn merge_keeps_the_generation_found() {
+        let mut first = HallOfFame::new(3);
+        first.update(&[individual(&[1, 0, 0])], &FitnessOrder::Max, 2);
+
+        let mut second = HallOfFame::new(3);
+        second.update(&[individual(&[1, 1, 1])], &FitnessOrder::Max, 7);
+        second.update(&[individual(&[1, 0, 0])], &FitnessOrder::Max, 9);
+
+        first.merge(&second, &FitnessOrder::Max);
+        assert_eq!(genomes(&first), vec![(vec![1, 1, 1], 7), (vec![1, 0, 0], 2)]);
+    }
+}
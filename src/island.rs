@@ -0,0 +1,362 @@
+/// # Island Model
+///
+/// several populations evolve independently, in parallel,
+/// and every so often exchange some of their members
+///
+/// each island is an `Engine` with its own rng seeded from the one passed in,
+/// so runs are still reproducible even though the islands step in parallel
+///
+/// the termination criteria are checked over the model as a whole, never by a single island,
+/// so one island stagnating doesn't stop the others
+use std::time::Instant;
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+use rand::seq::index;
+use rayon::prelude::*;
+
+use crate::{Engine, FitnessOrder, Generation, Individual, Model, Problem, RunSummary, StopReason, Termination};
+use crate::selection::Selection;
+
+/// which members of an island are sent to its neighbours
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Emigrants {
+    Best,
+    Random
+}
+
+/// which members of an island are replaced by incoming migrants
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Immigration {
+    ReplaceWorst,
+    ReplaceRandom
+}
+
+/// which islands send migrants to which
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Topology {
+    /// island i sends to island i + 1, wrapping round
+    Ring,
+    /// every island sends to every other island
+    FullyConnected,
+    /// every island sends to one other island, chosen at random each migration
+    Random
+}
+
+#[derive(Debug, Clone)]
+pub struct Migration {
+    /// generations between migrations
+    pub interval: usize,
+    /// migrants sent along each connection
+    pub migrants: usize,
+    pub emigrants: Emigrants,
+    pub immigration: Immigration,
+    pub topology: Topology
+}
+
+impl Default for Migration {
+    fn default() -> Self {
+        Migration {
+            interval: 50,
+            migrants: 2,
+            emigrants: Emigrants::Best,
+            immigration: Immigration::ReplaceWorst,
+            topology: Topology::Ring
+        }
+    }
+}
+
+pub struct IslandModel<P: Problem, S: Selection> {
+    islands: Vec<(Engine<P, S>, StdRng)>,
+    order: FitnessOrder,
+    termination: Termination,
+    migration: Migration,
+    best: Option<Individual<P::Genome>>,
+    best_average: Option<f64>,
+    since_improvement: usize,
+    started: Option<Instant>
+}
+
+impl<P, S> IslandModel<P, S>
+where
+    P: Problem + Clone + Send,
+    S: Selection + Clone + Send
+{
+    /// one island per generation given, which should already be initialised
+    /// every island uses its own copy of the problem, and the same selection and model
+    ///
+    /// the termination criteria apply to all the islands together:
+    /// generations are counted per island, evaluations are summed over the islands,
+    /// the target fitness and stagnation go by the best over every island and the average over every member,
+    /// and low diversity needs every island to have converged
+    #[allow(clippy::too_many_arguments)]
+    pub fn new<R: Rng + ?Sized>(problem: P, generations: Vec<Generation<P::Genome>>, order: FitnessOrder, selection: S, model: Model, termination: Termination, migration: Migration, rng: &mut R) -> Self {
+        let islands = generations.into_iter()
+            .map(|mut generation| {
+                if termination.min_diversity.is_some() {
+                    generation.set_diversity_tracking(true);
+                }
+
+                // the islands never stop by themselves, only the model does
+                let engine = Engine::new(problem.clone(), generation, order, selection.clone(), model.clone(), Termination::default());
+                (engine, StdRng::seed_from_u64(rng.gen()))
+            })
+            .collect();
+
+        let mut island_model = IslandModel {
+            islands,
+            order,
+            termination,
+            migration,
+            best: None,
+            best_average: None,
+            since_improvement: 0,
+            started: None
+        };
+        island_model.best = island_model.current_best().cloned();
+        island_model.best_average = Some(island_model.average_fitness());
+        island_model
+    }
+
+    pub fn islands(&self) -> impl Iterator<Item = &Generation<P::Genome>> {
+        self.islands.iter().map(|(engine, _)| engine.generation())
+    }
+
+    /// best individual seen so far on any island
+    pub fn best(&self) -> Option<&Individual<P::Genome>> {
+        self.best.as_ref()
+    }
+
+    /// step every island in parallel, then migrate if it's time to
+    /// returns the reason to stop if any criterion has been met
+    pub fn step<R: Rng + ?Sized>(&mut self, rng: &mut R) -> Option<StopReason> {
+        self.started.get_or_insert_with(Instant::now);
+
+        self.islands.par_iter_mut()
+            .for_each(|(engine, island_rng)| { engine.step(island_rng); });
+
+        if self.migration.interval > 0 && self.generation().is_multiple_of(self.migration.interval) {
+            self.migrate(rng);
+
+            // so the statistics, and anything reading them, see the migrants
+            let order = self.order;
+            for (engine, _) in self.islands.iter_mut() {
+                engine.generation_mut().update_statistics(&order);
+            }
+        }
+
+        self.since_improvement += 1;
+
+        // the islands' own bests include anything that migrated to them before the step
+        if let Some(current) = self.current_best() {
+            if self.best.as_ref().is_none_or(|best| self.order.compare_individuals(current, best).is_lt()) {
+                self.best = Some(current.clone());
+                self.since_improvement = 0;
+            }
+        }
+
+        let average = self.average_fitness();
+        if self.best_average.is_none_or(|best| self.order.compare(average, best).is_lt()) {
+            self.best_average = Some(average);
+            self.since_improvement = 0;
+        }
+
+        self.check_termination()
+    }
+
+    /// step until the termination criteria are met, with none set this never returns
+    pub fn run<R: Rng + ?Sized>(&mut self, rng: &mut R) -> RunSummary<P::Genome> {
+        loop {
+            if let Some(reason) = self.step(rng) {
+                return self.summary(reason);
+            }
+        }
+    }
+
+    /// summary over all islands, with evaluations summed, panics if every island was empty to begin with
+    pub fn summary(&self, reason: StopReason) -> RunSummary<P::Genome> {
+        RunSummary {
+            reason,
+            generations: self.generation(),
+            evaluations: self.evaluations(),
+            elapsed: self.started.map(|start| start.elapsed()).unwrap_or_default(),
+            best: self.best.clone().expect("empty population")
+        }
+    }
+
+    /// every island steps together, so they're all on the same generation
+    fn generation(&self) -> usize {
+        self.islands.first().map(|(engine, _)| engine.generation().get_generation()).unwrap_or(0)
+    }
+
+    fn evaluations(&self) -> usize {
+        self.islands.iter().map(|(engine, _)| engine.generation().get_evaluations()).sum()
+    }
+
+    fn current_best(&self) -> Option<&Individual<P::Genome>> {
+        self.islands.iter()
+            .filter_map(|(engine, _)| engine.best())
+            .min_by(|a, b| self.order.compare_individuals(a, b))
+    }
+
+    /// mean fitness over every member of every island
+    fn average_fitness(&self) -> f64 {
+        let (total, count) = self.islands.iter()
+            .map(|(engine, _)| engine.generation())
+            .fold((0.0, 0), |(total, count), generation| {
+                let len = generation.population.len();
+                (total + generation.get_average_fitness() * len as f64, count + len)
+            });
+        total / count.max(1) as f64
+    }
+
+    fn check_termination(&self) -> Option<StopReason> {
+        let termination = &self.termination;
+
+        if let (Some(target), Some(best)) = (termination.target_fitness, &self.best) {
+            if best.is_feasible() && self.order.compare(best.objective(), target).is_le() {
+                return Some(StopReason::TargetFitness);
+            }
+        }
+        if termination.max_generations.is_some_and(|max| self.generation() >= max) {
+            return Some(StopReason::MaxGenerations);
+        }
+        if termination.max_evaluations.is_some_and(|max| self.evaluations() >= max) {
+            return Some(StopReason::MaxEvaluations);
+        }
+        if let (Some(limit), Some(start)) = (termination.time_limit, self.started) {
+            if start.elapsed() >= limit {
+                return Some(StopReason::TimeLimit);
+            }
+        }
+        if termination.stagnation.is_some_and(|max| self.since_improvement >= max) {
+            return Some(StopReason::Stagnation);
+        }
+        if let Some(min) = termination.min_diversity {
+            let converged = self.islands.iter()
+                .all(|(engine, _)| engine.generation().get_diversity().is_some_and(|diversity| diversity < min));
+            if converged {
+                return Some(StopReason::LowDiversity);
+            }
+        }
+
+        None
+    }
+
+    /// all emigrants are chosen before any are placed, so migration is synchronous
+    fn migrate<R: Rng + ?Sized>(&mut self, rng: &mut R) {
+        let count = self.islands.len();
+        if count < 2 {
+            return;
+        }
+
+        // (destination, migrants) for every connection in the topology
+        let mut packets: Vec<(usize, Vec<Individual<P::Genome>>)> = Vec::new();
+        for source in 0..count {
+            let destinations: Vec<usize> = match self.migration.topology {
+                Topology::Ring => vec![(source + 1) % count],
+                Topology::FullyConnected => (0..count).filter(|dest| *dest != source).collect(),
+                Topology::Random => {
+                    // pick from the other islands, skipping over the source
+                    let dest = rng.gen_range(0..count - 1);
+                    vec![if dest >= source { dest + 1 } else { dest }]
+                }
+            };
+
+            for dest in destinations {
+                let migrants = self.emigrants(source, rng);
+                packets.push((dest, migrants));
+            }
+        }
+
+        for (dest, migrants) in packets {
+            self.immigrate(dest, migrants, rng);
+        }
+    }
+
+    fn emigrants<R: Rng + ?Sized>(&self, source: usize, rng: &mut R) -> Vec<Individual<P::Genome>> {
+        let population = &self.islands[source].0.generation().population;
+        let migrants = self.migration.migrants.min(population.len());
+
+        let indices: Vec<usize> = match self.migration.emigrants {
+            Emigrants::Best => {
+                let mut ranked: Vec<usize> = (0..population.len()).collect();
+                ranked.sort_by(|a, b| self.order.compare(population[*a].fitness(), population[*b].fitness()));
+                ranked.truncate(migrants);
+                ranked
+            },
+            Emigrants::Random => index::sample(rng, population.len(), migrants).into_vec()
+        };
+
+        indices.into_iter().map(|idx| population[idx].clone()).collect()
+    }
+
+    fn immigrate<R: Rng + ?Sized>(&mut self, dest: usize, migrants: Vec<Individual<P::Genome>>, rng: &mut R) {
+        let order = self.order;
+        let population = &mut self.islands[dest].0.generation_mut().population;
+        let count = migrants.len().min(population.len());
+
+        let indices: Vec<usize> = match self.migration.immigration {
+            Immigration::ReplaceWorst => {
+                let mut ranked: Vec<usize> = (0..population.len()).collect();
+                ranked.sort_by(|a, b| order.compare(population[*b].fitness(), population[*a].fitness()));
+                ranked.truncate(count);
+                ranked
+            },
+            Immigration::ReplaceRandom => index::sample(rng, population.len(), count).into_vec()
+        };
+
+        for (idx, migrant) in indices.into_iter().zip(migrants) {
+            population[idx] = migrant;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bitstring::{Bitstring, BitstringProblem};
+    use crate::test_util::OneMax;
+    use crate::selection::Tournament;
+    use crate::initialise;
+
+    fn island_model(termination: Termination, rng: &mut StdRng) -> IslandModel<BitstringProblem<OneMax, Bitstring>, Tournament> {
+        let problem = BitstringProblem::new(40, OneMax);
+        let islands = (0..3)
+            .map(|_| {
+                let mut generation = Generation::new(10);
+                initialise(&mut generation, &problem, rng);
+                generation
+            })
+            .collect();
+        let migration = Migration { interval: 1, ..Migration::default() };
+        IslandModel::new(problem, islands, FitnessOrder::Max, Tournament::new(2), Model::default(), termination, migration, rng)
+    }
+
+    #[test]
+    fn statistics_include_migrants() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let mut model = island_model(Termination::default(), &mut rng);
+
+        for _ in 0..5 {
+            model.step(&mut rng);
+            for island in model.islands() {
+                let fittest = island.population.iter().map(|individual| individual.fitness()).fold(f64::MIN, f64::max);
+                assert_eq!(island.get_statistics().best, fittest);
+            }
+        }
+    }
+
+    #[test]
+    fn evaluations_are_counted_over_every_island() {
+        let mut rng = StdRng::seed_from_u64(2);
+        let termination = Termination { max_evaluations: Some(60), ..Termination::default() };
+        let mut model = island_model(termination, &mut rng);
+        let summary = model.run(&mut rng);
+
+        // stopped on the total, long before any one island made 60 evaluations
+        assert_eq!(summary.reason, StopReason::MaxEvaluations);
+        assert!(summary.evaluations >= 60);
+        assert_eq!(summary.evaluations, model.islands().map(|island| island.get_evaluations()).sum::<usize>());
+        assert!(model.islands().all(|island| island.get_evaluations() < 60));
+    }
+}
@@ -1,208 +1,510 @@
 use std::cmp::Ordering;
+use std::error::Error;
 use rayon::prelude::*;
-use rand::{thread_rng, Rng};
+use rand::Rng;
+use rand::seq::SliceRandom;
+
+pub mod selection;
+pub mod replacement;
+pub mod steady_state;
+pub mod engine;
+pub mod observer;
+pub mod statistics;
+pub mod island;
+pub mod nsga2;
+pub mod constraints;
+pub mod checkpoint;
+pub mod niching;
+pub mod diversity;
+pub mod hall_of_fame;
+pub mod operators;
+pub mod bitstring;
+pub mod real;
+pub mod permutation;
+pub mod discrete;
+pub mod gp;
+#[cfg(test)]
+mod test_util;
+
+use selection::Selection;
+use replacement::{deterministic_crowding, Replacement};
+use statistics::Statistics;
+use constraints::ConstraintHandling;
+use diversity::{eliminate_duplicates, restart_if_converged, Reseed, Restart};
+
+pub use engine::{Engine, Model, RunSummary, StopReason, Termination};
 
 /// # Genotype 
 /// the encoded model for phenotypic characteristics of a solution
+/// this is just the data, anything shared by every solution to a problem belongs to the `Problem`
 pub trait Genotype
 where Self: Sized + Clone {
+    /// how different two genomes are, 0 meaning identical
+    /// used by the replacement schemes that only compete similar solutions
+    fn distance(&self, other: &Self) -> f64;
+    /// hash that must be the same for any two genomes at distance 0, e.g. any rotation of a tour
+    /// only used to find duplicates quickly, the default puts every genome in the same bucket
+    fn canonical_hash(&self) -> u64
+        {0}
+    /// how big the genome is, e.g. the number of nodes in a tree, used by bloat control
+    /// genomes that don't grow can leave every genome the same size
+    fn size(&self) -> usize
+        {1}
+}
+
+/// # Variation
+/// how a problem's genomes are made and changed: random genomes, the operators and their rates,
+/// and whatever data they need, e.g. a tsp dataset
+/// shared by single-objective problems, see `Problem`, and multi-objective ones, see `nsga2::MultiObjective`
+///
+/// all randomness is drawn from the rng passed in, so that a seeded rng
+/// gives the same populations on every run
+pub trait Variation: Sync {
+    type Genome: Genotype + std::fmt::Debug + Send + Sync;
+    /// generate a new random genome, for populating an empty generation
+    fn random<R: Rng + ?Sized>(&self, rng: &mut R) -> Self::Genome;
     /// generate offspring of two parents
-    fn crossover(x: &Self, y: &Self) -> (Self, Self);
+    fn crossover<R: Rng + ?Sized>(&self, x: &Self::Genome, y: &Self::Genome, rng: &mut R) -> (Self::Genome, Self::Genome);
     /// randomised change
-    fn mutation(&self) -> Self;
-    /// generate a new randomised version of itself, for populating empty generation
-    /// this is actually irrelvant here, it made more sense in the exercise sheet scenarios
-    fn random() -> Self 
-        {unimplemented!()}  
-    /// calculate the fitness of this solution
-    fn fitness(&self) -> f64;
+    fn mutation<R: Rng + ?Sized>(&self, genome: &Self::Genome, rng: &mut R) -> Self::Genome;
+    /// `crossover`, along with the index of the operator that made the children, if any
+    /// only problems choosing between several operators need to implement this, see `operators::OperatorPool`
+    fn crossover_with_operator<R: Rng + ?Sized>(&self, x: &Self::Genome, y: &Self::Genome, rng: &mut R) -> (Self::Genome, Self::Genome, Option<usize>) {
+        let (child0, child1) = self.crossover(x, y, rng);
+        (child0, child1, None)
+    }
+    /// `mutation`, along with the index of the operator applied, if any
+    fn mutation_with_operator<R: Rng + ?Sized>(&self, genome: &Self::Genome, rng: &mut R) -> (Self::Genome, Option<usize>)
+        {(self.mutation(genome, rng), None)}
+}
+
+/// # Origin
+/// which operators made a child, kept alongside it until it's been evaluated and passed to `Problem::feedback`,
+/// so the genome doesn't have to carry it
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Origin {
+    pub crossover: Option<usize>,
+    pub mutation: Option<usize>
+}
+
+/// # Problem
+/// everything a population of genomes shares: the fitness function, along with its `Variation`
+/// held once by whatever runs the population, instead of once per genome
+///
+/// the genome modules, `bitstring`, `real`, `permutation`, `discrete` and `gp`, each come with a problem
+/// that's generic over a fitness trait, e.g. `bitstring::BitstringProblem` over `bitstring::BitstringFitness`
+/// the problem brings the random genomes and operators, so only the fitness needs writing
+pub trait Problem: Variation {
+    /// calculate the fitness of a solution
+    fn fitness(&self, genome: &Self::Genome) -> f64;
+    /// how far a solution is from satisfying each constraint, 0 meaning satisfied
+    /// unconstrained problems don't need to implement this
+    fn violations(&self, _genome: &Self::Genome) -> Vec<f64>
+        {Vec::new()}
+    /// turn an infeasible solution into a feasible one, used by `ConstraintHandling::Repair`
+    fn repair<R: Rng + ?Sized>(&self, genome: Self::Genome, _rng: &mut R) -> Self::Genome
+        {genome}
+    /// called once a child has been evaluated, with its fitness and that of the fitter of its parents,
+    /// so the problem can reward whichever operators made it, see `operators::OperatorPool`
+    /// problems with fixed operators don't need to implement this
+    fn feedback(&self, _child: &Self::Genome, _origin: &Origin, _parent_fitness: f64, _child_fitness: f64, _order: &FitnessOrder)
+        {}
+    /// anything the problem changes over a run, e.g. adapted operator weights, as checkpoint records
+    /// problems that never change don't need to implement this
+    fn save_state(&self) -> Vec<Vec<String>>
+        {Vec::new()}
+    /// restore the records written by `save_state`, when resuming from a checkpoint
+    fn load_state(&mut self, _records: &[Vec<String>]) -> Result<(), Box<dyn Error>>
+        {Ok(())}
+}
+
+/// A single member of the population
+/// fitness is evaluated once when the genome is set and cached alongside it,
+/// since for the tsp every call to `fitness()` is a full walk of the tour
+///
+/// the raw objective and constraint violations are cached too,
+/// and the fitness used for ranking is derived from them by the generation's `ConstraintHandling`
+#[derive(Debug, Clone)]
+pub struct Individual<T: Genotype> {
+    genome: T,
+    objective: f64,
+    violations: Vec<f64>,
+    fitness: f64,
+    born: usize
+}
+
+impl<T: Genotype> Individual<T> {
+    pub fn new<P: Problem<Genome = T>>(problem: &P, genome: T) -> Self {
+        let objective = problem.fitness(&genome);
+        let violations = problem.violations(&genome).into_iter().map(|v| v.max(0.0)).collect();
+        Individual { genome, objective, violations, fitness: objective, born: 0 }
+    }
+
+    pub fn genome(&self) -> &T {
+        &self.genome
+    }
+
+    pub fn into_genome(self) -> T {
+        self.genome
+    }
+
+    /// the cached fitness, never re-evaluated
+    /// this is the objective adjusted for any constraint violations, and is what everything ranks by
+    pub fn fitness(&self) -> f64 {
+        self.fitness
+    }
+
+    /// the cached value of `Problem::fitness`, ignoring constraints
+    pub fn objective(&self) -> f64 {
+        self.objective
+    }
+
+    /// the cached value of `Problem::violations`
+    pub fn violations(&self) -> &[f64] {
+        &self.violations
+    }
+
+    /// total of all constraint violations
+    pub fn violation(&self) -> f64 {
+        self.violations.iter().sum()
+    }
+
+    pub fn is_feasible(&self) -> bool {
+        self.violations.iter().all(|v| *v <= 0.0)
+    }
+
+    /// number of fitness evaluations made before this individual was created
+    pub fn born(&self) -> usize {
+        self.born
+    }
+
+    /// replace the genome, which invalidates and recalculates the cached fitness
+    /// the fitness is the raw objective until the constraint handling is next applied
+    pub fn set_genome<P: Problem<Genome = T>>(&mut self, problem: &P, genome: T) {
+        let born = self.born;
+        *self = Individual { born, ..Individual::new(problem, genome) };
+    }
 }
 
 /// Each individual generation, stored as a struct
 #[derive(Debug)]
 pub struct Generation<T: Genotype + std::fmt::Debug> {
-    pub population: Vec<T>,
-    temp_population: Vec<T>,
+    pub population: Vec<Individual<T>>,
     average_fitness: f64,
-    population_size: usize
+    population_size: usize,
+    evaluations: usize,
+    generation: usize,
+    statistics: Statistics,
+    /// one snapshot per generation, starting with the initial population
+    history: Vec<Statistics>,
+    constraints: ConstraintHandling,
+    duplicates: Option<Reseed>,
+    restart: Option<Restart>,
+    restarts: usize,
+    track_diversity: bool
 }
 
 impl<T: Genotype + std::fmt::Debug> Generation<T> {
     pub fn new(size: usize) -> Self {
         Generation {
             population: Vec::new(),
-            temp_population: Vec::new(),
             average_fitness: 0.0,
-            population_size: size
+            population_size: size,
+            evaluations: 0,
+            generation: 0,
+            statistics: Statistics::default(),
+            history: Vec::new(),
+            constraints: ConstraintHandling::default(),
+            duplicates: None,
+            restart: None,
+            restarts: 0,
+            track_diversity: false
         }
     }
 
+    /// how constraint violations affect fitness, ignored by default
+    pub fn set_constraint_handling(&mut self, constraints: ConstraintHandling) {
+        self.constraints = constraints;
+    }
+
+    pub fn get_constraint_handling(&self) -> &ConstraintHandling {
+        &self.constraints
+    }
+
+    /// replace duplicate genomes at the end of every epoch, kept by default
+    pub fn set_duplicate_elimination(&mut self, duplicates: Option<Reseed>) {
+        self.duplicates = duplicates;
+    }
+
+    pub fn get_duplicate_elimination(&self) -> Option<&Reseed> {
+        self.duplicates.as_ref()
+    }
+
+    /// restart the population when its diversity collapses, never by default
+    pub fn set_restart(&mut self, restart: Option<Restart>) {
+        self.restart = restart;
+    }
+
+    pub fn get_restart(&self) -> Option<&Restart> {
+        self.restart.as_ref()
+    }
+
+    /// work out the diversity and number of unique genomes along with the rest of the statistics, off by default
+    /// every pair of genomes is compared, which can cost more than the fitness evaluations,
+    /// so the engine only turns this on when a termination criterion or observer needs it
+    pub fn set_diversity_tracking(&mut self, track: bool) {
+        self.track_diversity = track;
+    }
+
+    /// whether the diversity statistics are being worked out, always if there's a restart policy
+    pub fn tracks_diversity(&self) -> bool {
+        self.track_diversity || self.restart.is_some()
+    }
+
+    /// number of times the population has been restarted
+    pub fn get_restarts(&self) -> usize {
+        self.restarts
+    }
+
     pub fn get_average_fitness(&self) -> f64 {
         self.average_fitness
     }
 
-    pub fn get_best_solution(&mut self, order: &FitnessOrder) -> T {
-        if *order == FitnessOrder::Max {
-            // sort by fitness (in descending order)
-            self.population.sort_by(|a, b| {
-                if a.fitness() > b.fitness() {
-                    Ordering::Less
-                } else if a.fitness() < b.fitness() {
-                    Ordering::Greater
-                } else {
-                    Ordering::Equal
-                }
-            });
-        } else {
-            // sort by fitness (in ascending order)
-            self.population.sort_by(|a, b| {
-                if a.fitness() < b.fitness() {
-                    Ordering::Less
-                } else if a.fitness() > b.fitness() {
-                    Ordering::Greater
-                } else {
-                    Ordering::Equal
-                }
-            });
-        }
-        self.population.first().unwrap().clone()
-    }
-
-    pub fn get_best_fitness(&mut self, order: &FitnessOrder) -> f64 {
-        if *order == FitnessOrder::Max {
-            // sort by fitness (in descending order)
-            self.population.sort_by(|a, b| {
-                if a.fitness() > b.fitness() {
-                    Ordering::Less
-                } else if a.fitness() < b.fitness() {
-                    Ordering::Greater
-                } else {
-                    Ordering::Equal
-                }
-            });
-        } else {
-            // sort by fitness (in ascending order)
-            self.population.sort_by(|a, b| {
-                if a.fitness() < b.fitness() {
-                    Ordering::Less
-                } else if a.fitness() > b.fitness() {
-                    Ordering::Greater
-                } else {
-                    Ordering::Equal
-                }
-            });
-        }
-        self.population.first().unwrap().fitness()
+    /// the fittest member of the population, read from the cached fitness
+    pub fn get_best_individual(&self, order: &FitnessOrder) -> &Individual<T> {
+        self.population.iter()
+            .min_by(|a, b| order.compare(a.fitness(), b.fitness()))
+            .unwrap()
+    }
+
+    pub fn get_best_solution(&self, order: &FitnessOrder) -> T {
+        self.get_best_individual(order).genome().clone()
+    }
+
+    pub fn get_best_fitness(&self, order: &FitnessOrder) -> f64 {
+        self.get_best_individual(order).fitness()
     }
 
     pub fn get_population_size(&self) -> usize {
         self.population_size
     }
 
-    pub fn push(&mut self, item: T) {
-        self.population.push(item);
+    /// total number of fitness evaluations made so far
+    pub fn get_evaluations(&self) -> usize {
+        self.evaluations
     }
-}
 
-/// initialise with random, unseeded population
-pub fn initialise<T: Genotype + std::fmt::Debug>(gen: &mut Generation<T>) {
-    for _ in 0..gen.population_size {
-        gen.population.push(T::random());
+    /// number of epochs run so far
+    pub fn get_generation(&self) -> usize {
+        self.generation
     }
-}
 
-// enum to determine how to determine whether we want max or min fitness
-#[derive(PartialEq)]
-pub enum FitnessOrder {Max, Min}
+    /// snapshot of the population taken at the end of the last epoch
+    pub fn get_statistics(&self) -> &Statistics {
+        &self.statistics
+    }
 
-/// head-to-head tournament selection based on fitness
-fn tournament_selection<T: Genotype>(solutions: &[T], order: &FitnessOrder) -> T {
-    let mut rng = thread_rng(); 
-    let s0_index = rng.gen_range(0..solutions.len());
-    let s1_index = rng.gen_range(0..solutions.len());
-
-    // if order == max return higher fitness, if min return lower fitness
-    if 
-        (*order == FitnessOrder::Max 
-            && solutions.get(s0_index).unwrap().fitness() > solutions.get(s1_index).unwrap().fitness()) 
-            || 
-        (*order == FitnessOrder::Min 
-            && solutions.get(s0_index).unwrap().fitness() < solutions.get(s1_index).unwrap().fitness()) {
-        solutions.get(s0_index).unwrap().clone()
-    } else {
-        solutions.get(s1_index).unwrap().clone()
+    /// every snapshot taken so far, indexed by generation
+    pub fn get_history(&self) -> &[Statistics] {
+        &self.history
+    }
+
+    /// mean pairwise distance between members of the population, as of the last epoch
+    /// 0 means every member is identical, `None` if diversity isn't being tracked
+    pub fn get_diversity(&self) -> Option<f64> {
+        self.statistics.diversity
+    }
+
+    /// evaluate and add a new member to the population
+    pub fn push<P: Problem<Genome = T>>(&mut self, problem: &P, item: T) {
+        let mut individual = Individual::new(problem, item);
+        individual.born = self.evaluations;
+        self.evaluations += 1;
+
+        // keep the running average up to date, so it is valid before the first epoch
+        let len = self.population.len() as f64;
+        self.average_fitness = (self.average_fitness * len + individual.fitness()) / (len + 1.0);
+        self.population.push(individual);
     }
 }
 
-pub fn epoch<T: Genotype + std::fmt::Debug + Sync + Send>(gen: &mut Generation<T>, order: &FitnessOrder) {
-    let mut rng = thread_rng();
+impl<T: Genotype + std::fmt::Debug + Sync + Send> Generation<T> {
+    /// take a new statistics snapshot, and update the average fitness from it
+    /// this is quadratic in the population size if diversity is being tracked, see `Statistics::new`
+    pub fn update_statistics(&mut self, order: &FitnessOrder) {
+        self.statistics = Statistics::new(&self.population, order, self.tracks_diversity());
+        self.average_fitness = self.statistics.mean;
 
-    // determine sorted order, whether low to high fitness (min) or high to low (max)
-    // done this way so that solution at index 0 is most fit
-    if *order == FitnessOrder::Max {
-        // sort by fitness (in descending order)
-        gen.population.par_sort_by(|a, b| {
-            if a.fitness() > b.fitness() {
-                Ordering::Less
-            } else if a.fitness() < b.fitness() {
-                Ordering::Greater
-            } else {
-                Ordering::Equal
+        // a second snapshot in the same generation replaces the first
+        self.history.truncate(self.generation);
+        self.history.push(self.statistics.clone());
+    }
+
+    /// everything done at the end of an epoch, once the new population is in place
+    pub(crate) fn finish_epoch<P, R>(&mut self, problem: &P, order: &FitnessOrder, rng: &mut R)
+    where
+        P: Problem<Genome = T>,
+        R: Rng + ?Sized
+    {
+        self.generation += 1;
+        eliminate_duplicates(self, problem, order, rng);
+
+        // get average fitness and the rest of the statistics for the new generation
+        self.update_statistics(order);
+        if restart_if_converged(self, problem, order, rng) {
+            self.update_statistics(order);
+        }
+    }
+
+    /// recalculate the fitness of the population and offspring from their objectives and violations
+    /// they are done together, as feasibility rules rank against the worst feasible of either
+    pub(crate) fn apply_constraints(&mut self, offspring: &mut [Individual<T>], order: &FitnessOrder) {
+        if !self.constraints.adjusts_fitness() {
+            return;
+        }
+
+        let mut individuals: Vec<&mut Individual<T>> = self.population.iter_mut().chain(offspring.iter_mut()).collect();
+        self.constraints.apply(&mut individuals, order, self.generation);
+    }
+
+    /// crossover and mutate consecutive pairs of parents, given as indices into the population
+    /// an odd parent out is left unbred
+    fn breed<P: Problem<Genome = T>, R: Rng + ?Sized>(&self, problem: &P, parents: &[usize], rng: &mut R) -> (Vec<T>, Vec<Origin>) {
+        let mut offspring = Vec::with_capacity(parents.len());
+        let mut origins = Vec::with_capacity(parents.len());
+        for pair in parents.chunks_exact(2) {
+            let parent0 = self.population[pair[0]].genome();
+            let parent1 = self.population[pair[1]].genome();
+            let (child0, child1, crossover) = problem.crossover_with_operator(parent0, parent1, rng);
+
+            // perform mutations in this step as well
+            for child in [child0, child1] {
+                let (child, mutation) = problem.mutation_with_operator(&child, rng);
+                offspring.push(child);
+                origins.push(Origin { crossover, mutation });
             }
-        });
-    } else {
-        // sort by fitness (in ascending order)
-        gen.population.par_sort_by(|a, b| {
-            if a.fitness() < b.fitness() {
-                Ordering::Less
-            } else if a.fitness() > b.fitness() {
-                Ordering::Greater
-            } else {
-                Ordering::Equal
+        }
+
+        if self.constraints == ConstraintHandling::Repair {
+            offspring = offspring.into_iter().map(|child| problem.repair(child, rng)).collect();
+        }
+        (offspring, origins)
+    }
+
+    /// tell the problem how each child did against the fitter of its parents,
+    /// where the children were bred from consecutive pairs of `parents` by `breed`, which also gave their `origins`
+    /// must be called before the parents are replaced, as they're indices into the population
+    fn feedback<P: Problem<Genome = T>>(&self, problem: &P, parents: &[usize], offspring: &[Individual<T>], origins: &[Origin], order: &FitnessOrder) {
+        for ((pair, children), origins) in parents.chunks_exact(2).zip(offspring.chunks(2)).zip(origins.chunks(2)) {
+            let parent_fitness = pair.iter()
+                .map(|idx| self.population[*idx].fitness())
+                .min_by(|a, b| order.compare(*a, *b))
+                .unwrap();
+
+            for (child, origin) in children.iter().zip(origins) {
+                problem.feedback(child.genome(), origin, parent_fitness, child.fitness(), order);
             }
-        });
+        }
     }
 
-    // keep best n solutions
-    let best_n = 2;     // currently just keeping the top 2
-    for i in 0..best_n {
-        gen.temp_population.push(gen.population.get(i).unwrap().clone());
+    /// evaluate each child exactly once, in parallel
+    fn evaluate<P: Problem<Genome = T>>(&mut self, problem: &P, offspring: Vec<T>) -> Vec<Individual<T>> {
+        let born = self.evaluations;
+        self.evaluations += offspring.len();
+        offspring.into_par_iter()
+            .enumerate()
+            .map(|(idx, genome)| Individual { born: born + idx, ..Individual::new(problem, genome) })
+            .collect()
     }
+}
 
-    // set temp_pop from n to population_size with 2-element tournaments
-    for _ in best_n..gen.population_size {
-        gen.temp_population.push(tournament_selection(&gen.population, order));
+/// initialise with random population, drawn from the given rng
+/// random members are repaired first if the generation uses `ConstraintHandling::Repair`
+pub fn initialise<P: Problem, R: Rng + ?Sized>(gen: &mut Generation<P::Genome>, problem: &P, rng: &mut R) {
+    for _ in 0..gen.population_size {
+        let mut genome = problem.random(rng);
+        if gen.constraints == ConstraintHandling::Repair {
+            genome = problem.repair(genome, rng);
+        }
+        gen.push(problem, genome);
     }
+}
 
-    // clear out old population
-    gen.population.clear();
+/// sample from the standard normal distribution, using the box-muller transform
+pub fn gaussian<R: Rng + ?Sized>(rng: &mut R) -> f64 {
+    // 1 - u keeps the log away from 0
+    let u0: f64 = 1.0 - rng.gen::<f64>();
+    let u1: f64 = rng.gen();
+    (-2.0 * u0.ln()).sqrt() * (std::f64::consts::TAU * u1).cos()
+}
 
-    // stronger elitism - keep the best n solutions unchanged
-    for i in 0..best_n {
-        gen.population.push(gen.temp_population.get(i).unwrap().clone());
-    }
+// enum to determine how to determine whether we want max or min fitness
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FitnessOrder {Max, Min}
 
-    // perform crossover on all pairs without replacement
-    for _ in best_n..=gen.population_size / 2 {
-        if let (Some(parent0), Some(parent1)) = (
-            gen.temp_population.get(rng.gen_range(0..gen.population_size)),
-            gen.temp_population.get(rng.gen_range(0..gen.population_size))
-        ) {
-            let (child0, child1) = Genotype::crossover(parent0, parent1);
+impl FitnessOrder {
+    /// compare two fitness values so that the fitter one is ordered first
+    /// i.e. `Ordering::Less` means `a` is fitter than `b`
+    pub fn compare(&self, a: f64, b: f64) -> Ordering {
+        let ordering = if *self == FitnessOrder::Max {
+            b.partial_cmp(&a)
+        } else {
+            a.partial_cmp(&b)
+        };
+        ordering.unwrap_or(Ordering::Equal)
+    }
 
-            // perform mutations in this step as well
-            gen.population.push(child0.mutation());
-            gen.population.push(child1.mutation());
+    /// compare two individuals so that the better one is ordered first, for comparing across generations
+    /// feasible individuals beat infeasible ones, feasible ones are compared by objective, and infeasible ones by total violation
+    /// unlike the fitness, which constraint handling can rank against the rest of the population or the generation number,
+    /// this never changes, so it's what the best so far, the target fitness and the hall of fame use
+    /// with no constraints it's the same as comparing fitness
+    pub fn compare_individuals<T: Genotype>(&self, a: &Individual<T>, b: &Individual<T>) -> Ordering {
+        match (a.is_feasible(), b.is_feasible()) {
+            (true, true) => self.compare(a.objective(), b.objective()),
+            (true, false) => Ordering::Less,
+            (false, true) => Ordering::Greater,
+            (false, false) => a.violation().partial_cmp(&b.violation()).unwrap_or(Ordering::Equal)
         }
     }
+}
+
+/// run one generation, choosing parents with the given selection strategy
+/// and merging their offspring back in with the given replacement scheme
+pub fn epoch<P, S, R>(gen: &mut Generation<P::Genome>, problem: &P, order: &FitnessOrder, selection: &S, replacement: &Replacement, rng: &mut R)
+where
+    P: Problem,
+    S: Selection,
+    R: Rng + ?Sized
+{
+    // fitness may be stale if the population was pushed to, or the penalty depends on the generation
+    gen.apply_constraints(&mut [], order);
+
+    // determine sorted order, whether low to high fitness (min) or high to low (max)
+    // done this way so that solution at index 0 is most fit
+    gen.population.par_sort_by(|a, b| order.compare(a.fitness(), b.fitness()));
+
+    if *replacement == Replacement::DeterministicCrowding {
+        deterministic_crowding(gen, problem, order, rng);
+    } else {
+        let count = replacement.offspring_count(gen.population_size);
+
+        // parents are bred in pairs, so round up and drop the spare child
+        let mut parents = selection.select(&gen.population, order, count + count % 2, rng);
 
-    // clear temp pop for next epoch
-    gen.temp_population.clear();
+        // shuffle the mating pool, some strategies return parents in population order
+        parents.shuffle(rng);
+
+        let (mut offspring, origins) = gen.breed(problem, &parents, rng);
+        offspring.truncate(count);
+        let mut offspring = gen.evaluate(problem, offspring);
+        gen.apply_constraints(&mut offspring, order);
+        gen.feedback(problem, &parents, &offspring, &origins, order);
+
+        let mut population = std::mem::take(&mut gen.population);
+        replacement.replace(&mut population, offspring, gen.population_size, order, rng);
+        gen.population = population;
+    }
 
-    // get average fitness of generation
-    let mut fitness: f64 = gen.population.par_iter()
-        .map(|solution| solution.fitness())
-        .sum();
-    fitness /= gen.population_size as f64;
-    gen.average_fitness = fitness;
+    gen.finish_epoch(problem, order, rng);
 }
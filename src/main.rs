@@ -2,18 +2,42 @@ use std::thread;
 
 mod travelling_salesperson;
 
+/// seed for every dataset's grid search, change this to get a different run
+const SEED: u64 = 42;
+
+/// evolve the mutation and crossover rates along with the tours, instead of grid searching them
+const SELF_ADAPTIVE: bool = false;
+
+/// split the population between islands that exchange tours, instead of grid searching, see `island`
+const ISLANDS: bool = false;
+
+/// trade the length of each tour against its longest leg with NSGA-II, instead of grid searching, see `nsga2`
+const PARETO: bool = false;
+
+fn analyse_dataset(filepath: &str) -> Result<(), Box<dyn std::error::Error>> {
+    if PARETO {
+        travelling_salesperson::analyse_dataset_pareto(filepath, SEED)
+    } else if ISLANDS {
+        travelling_salesperson::analyse_dataset_islands(filepath, SEED)
+    } else if SELF_ADAPTIVE {
+        travelling_salesperson::analyse_dataset_self_adaptive(filepath, SEED)
+    } else {
+        travelling_salesperson::analyse_dataset(filepath, SEED)
+    }
+}
+
 fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     let berlin = thread::spawn(|| {
-        let _ = travelling_salesperson::analyse_dataset("./datasets/berlin52.tsp");
+        let _ = analyse_dataset("./datasets/berlin52.tsp");
     });
 
     let kro = thread::spawn(|| {
-        let _ = travelling_salesperson::analyse_dataset("./datasets/kroA100.tsp");
+        let _ = analyse_dataset("./datasets/kroA100.tsp");
     });
 
     let pr = thread::spawn(|| {
-        let _ = travelling_salesperson::analyse_dataset("./datasets/pr1002.tsp");
+        let _ = analyse_dataset("./datasets/pr1002.tsp");
     });
 
     berlin.join().unwrap();
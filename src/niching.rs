@@ -0,0 +1,288 @@
+/// # Niching
+///
+/// ways of keeping several peaks of a multi-modal problem in the population at once,
+/// instead of the whole population converging on whichever peak it finds first
+///
+/// - fitness sharing, see genetic algorithms with sharing for multimodal function optimization by GOLDBERG, RICHARDSON
+/// - clearing, see a clearing procedure as a niching method for genetic algorithms by PÉTROWSKI
+/// - restricted tournament selection, see finding multimodal solutions using restricted tournament selection by HARIK
+///
+/// sharing and clearing are adjustments, changing fitness by how crowded each member's niche is,
+/// which `Adjusted` puts in front of any of the strategies in `selection`
+/// adjustments compose, wrapping one `Adjusted` in another applies both, outermost first
+/// they're usually paired with fitness-proportionate selection, which the adjusted fitness affects the most
+/// restricted tournament is a selection strategy of its own, and also comes as a replacement scheme, see `Replacement::RestrictedTournament`
+/// similarity is always measured with `Genotype::distance`
+use rand::Rng;
+use rand::seq::index;
+
+use crate::{FitnessOrder, Genotype, Individual};
+use crate::selection::Selection;
+
+/// # Adjustment
+/// a change to the fitness selection sees that depends on the genomes, e.g. how crowded each member's niche is
+pub trait Adjustment {
+    /// adjust `fitness`, one value per member of `population`, which earlier adjustments may already have changed
+    fn adjust<T: Genotype, R: Rng + ?Sized>(&self, population: &[Individual<T>], order: &FitnessOrder, fitness: &mut [f64], rng: &mut R);
+}
+
+/// # Adjusted Selection
+/// adjust the fitness, then hand it on to the wrapped strategy
+#[derive(Debug, Clone)]
+pub struct Adjusted<A: Adjustment, S: Selection> {
+    pub adjustment: A,
+    pub selection: S
+}
+
+impl<A: Adjustment, S: Selection> Adjusted<A, S> {
+    pub fn new(adjustment: A, selection: S) -> Self {
+        Adjusted { adjustment, selection }
+    }
+}
+
+impl<A: Adjustment, S: Selection> Selection for Adjusted<A, S> {
+    /// without the genomes there's nothing to adjust, so this just defers to the wrapped strategy
+    /// selecting from a population always goes through `select_with_fitness`, which does adjust
+    fn select_by_fitness<R: Rng + ?Sized>(&self, fitness: &[f64], order: &FitnessOrder, count: usize, rng: &mut R) -> Vec<usize> {
+        self.selection.select_by_fitness(fitness, order, count, rng)
+    }
+
+    fn select_with_fitness<T: Genotype, R: Rng + ?Sized>(&self, population: &[Individual<T>], fitness: &[f64], order: &FitnessOrder, count: usize, rng: &mut R) -> Vec<usize> {
+        let mut adjusted = fitness.to_vec();
+        self.adjustment.adjust(population, order, &mut adjusted, rng);
+        self.selection.select_with_fitness(population, &adjusted, order, count, rng)
+    }
+}
+
+/// # Fitness Sharing
+/// each member's fitness is divided by its niche count,
+/// the sum of `1 - (d / radius)^alpha` over every member within `radius` of it, including itself
+/// so members of a crowded niche share its fitness between them
+///
+/// fitness should be non-negative, when minimising the fitness is multiplied by the niche count instead
+#[derive(Debug, Clone)]
+pub struct FitnessSharing {
+    /// members further apart than this don't share
+    pub radius: f64,
+    /// shape of the sharing function, 1 being triangular
+    pub alpha: f64
+}
+
+impl FitnessSharing {
+    /// triangular sharing
+    pub fn new(radius: f64) -> Self {
+        FitnessSharing { radius, alpha: 1.0 }
+    }
+}
+
+impl Adjustment for FitnessSharing {
+    fn adjust<T: Genotype, R: Rng + ?Sized>(&self, population: &[Individual<T>], order: &FitnessOrder, fitness: &mut [f64], _rng: &mut R) {
+        let len = population.len();
+
+        // every member is in its own niche, so counts start at 1
+        let mut niche_counts = vec![1.0; len];
+        for i in 0..len {
+            for j in i + 1..len {
+                let distance = population[i].genome().distance(population[j].genome());
+                if distance < self.radius {
+                    let share = 1.0 - (distance / self.radius).powf(self.alpha);
+                    niche_counts[i] += share;
+                    niche_counts[j] += share;
+                }
+            }
+        }
+
+        for (value, niche_count) in fitness.iter_mut().zip(niche_counts) {
+            if *order == FitnessOrder::Max {
+                *value /= niche_count;
+            } else {
+                *value *= niche_count;
+            }
+        }
+    }
+}
+
+/// # Clearing
+/// going from fittest to least fit, each member not yet cleared wins its niche,
+/// and only the best `capacity` members within `radius` of a winner keep their fitness
+/// the rest are cleared, their fitness set to that of the least fit member of the population
+#[derive(Debug, Clone)]
+pub struct Clearing {
+    /// members closer than this are in the same niche
+    pub radius: f64,
+    /// how many members of each niche keep their fitness, including the winner
+    pub capacity: usize
+}
+
+impl Clearing {
+    /// only the winner of each niche keeps its fitness
+    pub fn new(radius: f64) -> Self {
+        Clearing { radius, capacity: 1 }
+    }
+}
+
+impl Adjustment for Clearing {
+    fn adjust<T: Genotype, R: Rng + ?Sized>(&self, population: &[Individual<T>], order: &FitnessOrder, fitness: &mut [f64], _rng: &mut R) {
+        let mut ranked: Vec<usize> = (0..population.len()).collect();
+        ranked.sort_by(|a, b| order.compare(fitness[*a], fitness[*b]));
+
+        let worst = ranked.last().map(|idx| fitness[*idx]).unwrap_or(0.0);
+        let mut cleared = vec![false; population.len()];
+
+        for (rank, winner) in ranked.iter().enumerate() {
+            if cleared[*winner] {
+                continue;
+            }
+
+            let mut winners = 1;
+            for other in &ranked[rank + 1..] {
+                if cleared[*other] || population[*winner].genome().distance(population[*other].genome()) >= self.radius {
+                    continue;
+                }
+
+                if winners < self.capacity {
+                    winners += 1;
+                } else {
+                    cleared[*other] = true;
+                    fitness[*other] = worst;
+                }
+            }
+        }
+    }
+}
+
+/// # Restricted Tournament Selection
+/// each parent is found by picking a member at random, and holding a tournament between it
+/// and the most similar of `window` others drawn at random, the fitter of the two winning
+/// so members only compete with their own niche, instead of the whole population
+///
+/// the replacement scheme of the same name does the same for offspring and the members they'd replace,
+/// see `Replacement::RestrictedTournament`
+#[derive(Debug, Clone)]
+pub struct RestrictedTournament {
+    pub window: usize
+}
+
+impl RestrictedTournament {
+    pub fn new(window: usize) -> Self {
+        RestrictedTournament { window }
+    }
+
+    /// hold `count` tournaments, where `similar` picks the contestant to face each member drawn out of the window
+    fn hold<R, F>(&self, fitness: &[f64], order: &FitnessOrder, count: usize, rng: &mut R, similar: F) -> Vec<usize>
+    where
+        R: Rng + ?Sized,
+        F: Fn(usize, Vec<usize>) -> usize
+    {
+        let len = fitness.len();
+        if len == 0 {
+            return Vec::new();
+        }
+
+        (0..count).map(|_| {
+            let member = rng.gen_range(0..len);
+            if len == 1 {
+                return member;
+            }
+
+            let window: Vec<usize> = index::sample(rng, len - 1, self.window.clamp(1, len - 1)).into_iter()
+                // skip over the member itself
+                .map(|idx| if idx >= member { idx + 1 } else { idx })
+                .collect();
+            let rival = similar(member, window);
+
+            if order.compare(fitness[rival], fitness[member]).is_lt() { rival } else { member }
+        }).collect()
+    }
+}
+
+impl Selection for RestrictedTournament {
+    /// without the genomes every member is as similar as any other, so this is a tournament with the first of the window
+    fn select_by_fitness<R: Rng + ?Sized>(&self, fitness: &[f64], order: &FitnessOrder, count: usize, rng: &mut R) -> Vec<usize> {
+        self.hold(fitness, order, count, rng, |_, window| window[0])
+    }
+
+    fn select_with_fitness<T: Genotype, R: Rng + ?Sized>(&self, population: &[Individual<T>], fitness: &[f64], order: &FitnessOrder, count: usize, rng: &mut R) -> Vec<usize> {
+        self.hold(fitness, order, count, rng, |member, window| {
+            let genome = population[member].genome();
+            window.into_iter()
+                .min_by(|a, b| genome.distance(population[*a].genome()).total_cmp(&genome.distance(population[*b].genome())))
+                .unwrap()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+    use crate::bitstring::{Bitstring, BitstringProblem};
+    use crate::selection::{StochasticUniversal, Tournament};
+    use crate::test_util::OneMax;
+
+    fn population(genomes: &[(&[u8], usize)]) -> Vec<Individual<Bitstring>> {
+        let problem: BitstringProblem<OneMax, Bitstring> = BitstringProblem::new(4, OneMax);
+        genomes.iter()
+            .flat_map(|(bits, copies)| (0..*copies).map(|_| Individual::new(&problem, Bitstring::new(bits.to_vec()))))
+            .collect()
+    }
+
+    /// remembers the fitness it was handed, and selects nobody
+    struct Record(RefCell<Vec<f64>>);
+
+    impl Selection for Record {
+        fn select_by_fitness<R: Rng + ?Sized>(&self, fitness: &[f64], _order: &FitnessOrder, _count: usize, _rng: &mut R) -> Vec<usize> {
+            *self.0.borrow_mut() = fitness.to_vec();
+            Vec::new()
+        }
+    }
+
+    #[test]
+    fn sharing_lowers_the_share_of_a_crowded_niche() {
+        let mut rng = StdRng::seed_from_u64(0);
+        // eight copies of one genome and a lone one, all equally fit,
+        // and a least fit member, which proportionate selection never picks
+        let population = population(&[(&[1, 1, 1, 0], 8), (&[0, 1, 1, 1], 1), (&[0, 0, 0, 0], 1)]);
+        let crowded = |parents: Vec<usize>| parents.into_iter().filter(|idx| *idx < 8).count();
+
+        let plain = StochasticUniversal.select(&population, &FitnessOrder::Max, 900, &mut rng);
+        assert!((799..=801).contains(&crowded(plain)));
+
+        let shared = Adjusted::new(FitnessSharing::new(1.0), StochasticUniversal).select(&population, &FitnessOrder::Max, 900, &mut rng);
+        assert!((449..=451).contains(&crowded(shared)));
+    }
+
+    #[test]
+    fn adjustments_compose() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let population = population(&[(&[1, 1, 1, 1], 1), (&[1, 1, 1, 0], 3)]);
+
+        // shared first, 4 / 2 and 3 / (10 / 3), then all but the best cleared
+        let selection = Adjusted::new(FitnessSharing::new(1.5), Adjusted::new(Clearing::new(1.5), Record(RefCell::new(Vec::new()))));
+        selection.select(&population, &FitnessOrder::Max, 2, &mut rng);
+
+        let fitness = selection.selection.selection.0.borrow();
+        for (value, expected) in fitness.iter().zip([2.0, 0.9, 0.9, 0.9]) {
+            assert!((value - expected).abs() < 1e-9, "{:?}", fitness);
+        }
+    }
+
+    #[test]
+    fn restricted_tournament_favours_its_own_niche() {
+        let mut rng = StdRng::seed_from_u64(2);
+        let population = population(&[(&[1, 1, 1, 1], 4), (&[0, 0, 0, 0], 4)]);
+        let weaker = |parents: Vec<usize>| parents.into_iter().filter(|idx| *idx >= 4).count();
+
+        // the weaker niche only wins a plain tournament against itself, a quarter of the time
+        let plain = Tournament::new(2).select(&population, &FitnessOrder::Max, 2000, &mut rng);
+        assert!(weaker(plain) < 600);
+
+        let restricted = RestrictedTournament::new(3).select(&population, &FitnessOrder::Max, 2000, &mut rng);
+        assert!(weaker(restricted) > 800);
+
+        assert!(RestrictedTournament::new(3).select(&population[..0], &FitnessOrder::Max, 5, &mut rng).is_empty());
+        assert_eq!(RestrictedTournament::new(3).select(&population[..1], &FitnessOrder::Max, 5, &mut rng), vec![0; 5]);
+    }
+}
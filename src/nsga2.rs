@@ -0,0 +1,394 @@
+/// # Multi-Objective Optimisation with NSGA-II
+///
+/// see a fast and elitist multiobjective genetic algorithm: NSGA-II by DEB, PRATAP, AGARWAL, MEYARIVAN
+///
+/// - non-dominated sorting into fronts
+/// - crowding distance within each front
+/// - binary tournament on (rank, crowding distance)
+/// - (μ+λ) survival, filling the next population front by front
+use std::error::Error;
+use std::io::Write;
+use csv::WriterBuilder;
+use rand::Rng;
+use rayon::prelude::*;
+
+use crate::{FitnessOrder, Genotype, Variation};
+use crate::checkpoint::Checkpoint;
+
+/// # Multi-Objective Problem
+/// a problem whose genomes are scored on several objectives at once, each with its own direction
+/// random genomes and the operators come from `Variation`, as for a single-objective `Problem`,
+/// so the same genome and operators can be used for both
+pub trait MultiObjective: Variation {
+    /// value of every objective for a genome, in the same order as `directions`
+    fn objectives(&self, genome: &Self::Genome) -> Vec<f64>;
+    /// whether each objective is maximised or minimised
+    fn directions(&self) -> Vec<FitnessOrder>;
+}
+
+/// a member of the NSGA-II population
+/// objectives are evaluated once and cached, like `Individual`
+#[derive(Debug, Clone)]
+pub struct ParetoIndividual<T: Genotype> {
+    genome: T,
+    objectives: Vec<f64>,
+    rank: usize,
+    crowding_distance: f64
+}
+
+impl<T: Genotype> ParetoIndividual<T> {
+    pub fn new<P: MultiObjective<Genome = T>>(problem: &P, genome: T) -> Self {
+        let objectives = problem.objectives(&genome);
+        ParetoIndividual {
+            genome,
+            objectives,
+            rank: 0,
+            crowding_distance: 0.0
+        }
+    }
+
+    pub fn genome(&self) -> &T {
+        &self.genome
+    }
+
+    pub fn objectives(&self) -> &[f64] {
+        &self.objectives
+    }
+
+    /// index of the front this individual is in, 0 being the non-dominated front
+    pub fn rank(&self) -> usize {
+        self.rank
+    }
+
+    /// infinite for the extremes of a front
+    pub fn crowding_distance(&self) -> f64 {
+        self.crowding_distance
+    }
+}
+
+/// whether `a` is at least as good as `b` in every objective, and better in at least one
+pub fn dominates(a: &[f64], b: &[f64], directions: &[FitnessOrder]) -> bool {
+    let mut better = false;
+    for ((x, y), direction) in a.iter().zip(b).zip(directions) {
+        match direction.compare(*x, *y) {
+            std::cmp::Ordering::Greater => return false,
+            std::cmp::Ordering::Less => better = true,
+            std::cmp::Ordering::Equal => {}
+        }
+    }
+    better
+}
+
+/// # Fast Non-Dominated Sort
+/// splits the points into fronts, returned as indices, the first being the non-dominated front
+pub fn non_dominated_sort(objectives: &[Vec<f64>], directions: &[FitnessOrder]) -> Vec<Vec<usize>> {
+    let len = objectives.len();
+    let mut dominated_by: Vec<Vec<usize>> = vec![Vec::new(); len];
+    let mut domination_count = vec![0; len];
+    let mut fronts = vec![Vec::new()];
+
+    for p in 0..len {
+        for q in 0..len {
+            if dominates(&objectives[p], &objectives[q], directions) {
+                dominated_by[p].push(q);
+            } else if dominates(&objectives[q], &objectives[p], directions) {
+                domination_count[p] += 1;
+            }
+        }
+        if domination_count[p] == 0 {
+            fronts[0].push(p);
+        }
+    }
+
+    let mut current = 0;
+    while !fronts[current].is_empty() {
+        let mut next = Vec::new();
+        for p in &fronts[current] {
+            for q in &dominated_by[*p] {
+                domination_count[*q] -= 1;
+                if domination_count[*q] == 0 {
+                    next.push(*q);
+                }
+            }
+        }
+        fronts.push(next);
+        current += 1;
+    }
+
+    // the loop always leaves an empty front on the end
+    fronts.pop();
+    fronts
+}
+
+/// # Crowding Distance
+/// for each point in a front, the sum over objectives of the normalised gap between its neighbours
+/// returned in the same order as `front`
+#[allow(clippy::needless_range_loop)]
+pub fn crowding_distance(front: &[usize], objectives: &[Vec<f64>]) -> Vec<f64> {
+    let len = front.len();
+    let mut distance = vec![0.0; len];
+    if len == 0 {
+        return distance;
+    }
+
+    let dimensions = objectives[front[0]].len();
+    for m in 0..dimensions {
+        let mut sorted: Vec<usize> = (0..len).collect();
+        sorted.sort_by(|a, b| objectives[front[*a]][m].total_cmp(&objectives[front[*b]][m]));
+
+        let min = objectives[front[sorted[0]]][m];
+        let max = objectives[front[sorted[len - 1]]][m];
+
+        distance[sorted[0]] = f64::INFINITY;
+        distance[sorted[len - 1]] = f64::INFINITY;
+
+        if max - min <= 0.0 {
+            continue;
+        }
+
+        for k in 1..len.saturating_sub(1) {
+            let gap = objectives[front[sorted[k + 1]]][m] - objectives[front[sorted[k - 1]]][m];
+            distance[sorted[k]] += gap / (max - min);
+        }
+    }
+
+    distance
+}
+
+#[derive(Debug)]
+pub struct Nsga2<P: MultiObjective> {
+    problem: P,
+    population: Vec<ParetoIndividual<P::Genome>>,
+    population_size: usize,
+    directions: Vec<FitnessOrder>,
+    evaluations: usize,
+    generation: usize,
+    /// whether rank and crowding distance are up to date
+    ranked: bool
+}
+
+impl<P: MultiObjective> Nsga2<P> {
+    pub fn new(problem: P, size: usize) -> Self {
+        Nsga2 {
+            directions: problem.directions(),
+            problem,
+            population: Vec::new(),
+            population_size: size,
+            evaluations: 0,
+            generation: 0,
+            ranked: false
+        }
+    }
+
+    /// initialise with random population, drawn from the given rng
+    pub fn initialise<R: Rng + ?Sized>(&mut self, rng: &mut R) {
+        let genomes = (0..self.population_size).map(|_| self.problem.random(rng)).collect();
+        self.population = self.evaluate(genomes);
+        self.ranked = false;
+    }
+
+    /// evaluate and add a new member to the population
+    pub fn push(&mut self, item: P::Genome) {
+        self.evaluations += 1;
+        self.population.push(ParetoIndividual::new(&self.problem, item));
+        self.ranked = false;
+    }
+
+    pub fn problem(&self) -> &P {
+        &self.problem
+    }
+
+    pub fn population(&self) -> &[ParetoIndividual<P::Genome>] {
+        &self.population
+    }
+
+    pub fn get_evaluations(&self) -> usize {
+        self.evaluations
+    }
+
+    pub fn get_generation(&self) -> usize {
+        self.generation
+    }
+
+    /// the non-dominated members of the current population
+    pub fn pareto_front(&self) -> Vec<&ParetoIndividual<P::Genome>> {
+        let objectives: Vec<Vec<f64>> = self.population.iter().map(|individual| individual.objectives.clone()).collect();
+        non_dominated_sort(&objectives, &self.directions)
+            .first()
+            .map(|front| front.iter().map(|idx| &self.population[*idx]).collect())
+            .unwrap_or_default()
+    }
+
+    /// run one generation of NSGA-II
+    pub fn epoch<R: Rng + ?Sized>(&mut self, rng: &mut R) {
+        // the tournament needs ranks, which aren't known for a freshly initialised population
+        if !self.ranked {
+            self.assign_ranks();
+        }
+
+        // binary crowded tournaments to fill the mating pool
+        let parents: Vec<usize> = (0..self.population_size + self.population_size % 2)
+            .map(|_| self.crowded_tournament(rng))
+            .collect();
+
+        let mut offspring = Vec::with_capacity(parents.len());
+        for pair in parents.chunks_exact(2) {
+            let (child0, child1) = self.problem.crossover(self.population[pair[0]].genome(), self.population[pair[1]].genome(), rng);
+            offspring.push(self.problem.mutation(&child0, rng));
+            offspring.push(self.problem.mutation(&child1, rng));
+        }
+        offspring.truncate(self.population_size);
+
+        // parents and children compete for the next population
+        let mut combined = std::mem::take(&mut self.population);
+        combined.extend(self.evaluate(offspring));
+
+        let objectives: Vec<Vec<f64>> = combined.iter().map(|individual| individual.objectives.clone()).collect();
+        let mut next = Vec::with_capacity(self.population_size);
+
+        for (rank, front) in non_dominated_sort(&objectives, &self.directions).into_iter().enumerate() {
+            if next.len() >= self.population_size {
+                break;
+            }
+
+            let distances = crowding_distance(&front, &objectives);
+            let mut members: Vec<(usize, f64)> = front.into_iter().zip(distances).collect();
+
+            // the last front to fit is cut down, keeping the least crowded
+            if next.len() + members.len() > self.population_size {
+                members.sort_by(|a, b| b.1.total_cmp(&a.1));
+                members.truncate(self.population_size - next.len());
+            }
+
+            for (idx, distance) in members {
+                let mut individual = combined[idx].clone();
+                individual.rank = rank;
+                individual.crowding_distance = distance;
+                next.push(individual);
+            }
+        }
+
+        self.population = next;
+        self.ranked = true;
+        self.generation += 1;
+    }
+
+    /// evaluate each child's objectives exactly once, in parallel
+    fn evaluate(&mut self, genomes: Vec<P::Genome>) -> Vec<ParetoIndividual<P::Genome>> {
+        self.evaluations += genomes.len();
+        let problem = &self.problem;
+        genomes.into_par_iter().map(|genome| ParetoIndividual::new(problem, genome)).collect()
+    }
+
+    /// recalculate rank and crowding distance for the whole population
+    fn assign_ranks(&mut self) {
+        let objectives: Vec<Vec<f64>> = self.population.iter().map(|individual| individual.objectives.clone()).collect();
+        for (rank, front) in non_dominated_sort(&objectives, &self.directions).into_iter().enumerate() {
+            let distances = crowding_distance(&front, &objectives);
+            for (idx, distance) in front.into_iter().zip(distances) {
+                self.population[idx].rank = rank;
+                self.population[idx].crowding_distance = distance;
+            }
+        }
+        self.ranked = true;
+    }
+
+    /// lower rank wins, then higher crowding distance
+    fn crowded_tournament<R: Rng + ?Sized>(&self, rng: &mut R) -> usize {
+        let a = rng.gen_range(0..self.population.len());
+        let b = rng.gen_range(0..self.population.len());
+        let (x, y) = (&self.population[a], &self.population[b]);
+
+        if x.rank < y.rank || (x.rank == y.rank && x.crowding_distance >= y.crowding_distance) {
+            a
+        } else {
+            b
+        }
+    }
+}
+
+impl<P> Nsga2<P>
+where
+    P: MultiObjective,
+    P::Genome: Checkpoint
+{
+    /// one row per member of the pareto front, `[objective_0, ..., objective_n, genome...]`
+    /// the genome is written with `Checkpoint::to_fields`, so takes as many fields as it needs, like the hall of fame
+    pub fn write_pareto_front<W: Write>(&self, inner: W) -> Result<(), Box<dyn Error>> {
+        let mut writer = WriterBuilder::new()
+            .flexible(true)
+            .from_writer(inner);
+
+        let mut header: Vec<String> = (0..self.directions.len())
+            .map(|m| format!("objective_{}", m))
+            .collect();
+        header.push("genome".to_string());
+        writer.write_record(&header)?;
+
+        for individual in self.pareto_front() {
+            let mut record: Vec<String> = individual.objectives.iter().map(|o| o.to_string()).collect();
+            record.extend(individual.genome.to_fields());
+            writer.write_record(&record)?;
+        }
+
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MIN_MIN: [FitnessOrder; 2] = [FitnessOrder::Min, FitnessOrder::Min];
+
+    #[test]
+    fn domination_respects_direction() {
+        assert!(dominates(&[1.0, 1.0], &[2.0, 1.0], &MIN_MIN));
+        assert!(!dominates(&[1.0, 1.0], &[1.0, 1.0], &MIN_MIN));
+        assert!(!dominates(&[1.0, 3.0], &[2.0, 1.0], &MIN_MIN));
+        assert!(dominates(&[2.0, 1.0], &[1.0, 1.0], &[FitnessOrder::Max, FitnessOrder::Min]));
+    }
+
+    #[test]
+    fn non_dominated_sort_splits_into_fronts() {
+        let objectives = vec![
+            vec![1.0, 4.0],
+            vec![2.0, 2.0],
+            vec![4.0, 1.0],
+            vec![3.0, 3.0],
+            vec![5.0, 5.0],
+            vec![2.0, 2.0]
+        ];
+        let mut fronts = non_dominated_sort(&objectives, &MIN_MIN);
+        for front in fronts.iter_mut() {
+            front.sort();
+        }
+
+        // duplicates don't dominate each other, so both copies of (2, 2) share the first front
+        assert_eq!(fronts, vec![vec![0, 1, 2, 5], vec![3], vec![4]]);
+        assert!(non_dominated_sort(&[], &MIN_MIN).is_empty());
+    }
+
+    #[test]
+    fn crowding_distance_is_infinite_at_the_extremes() {
+        let objectives = vec![
+            vec![0.0, 4.0],
+            vec![1.0, 3.0],
+            vec![3.0, 1.0],
+            vec![4.0, 0.0]
+        ];
+        // out of order on purpose, the distances come back in the order of the front
+        let front = vec![2, 0, 3, 1];
+        let distance = crowding_distance(&front, &objectives);
+
+        assert_eq!(distance[1], f64::INFINITY);
+        assert_eq!(distance[2], f64::INFINITY);
+        // each objective spans 4, point 1 sits in a gap of 3 in both, point 2 likewise
+        assert!((distance[3] - 1.5).abs() < 1e-12);
+        assert!((distance[0] - 1.5).abs() < 1e-12);
+
+        assert_eq!(crowding_distance(&[0], &objectives), vec![f64::INFINITY]);
+        assert!(crowding_distance(&[], &objectives).is_empty());
+    }
+}
@@ -0,0 +1,154 @@
+/// # Observers
+///
+/// hooks called by `Engine::run` at the start of a run, after every generation,
+/// whenever the best individual improves, and when the run terminates
+///
+/// logging, plotting and progress reporting are all observers,
+/// so they can be mixed and matched per experiment
+use std::error::Error;
+use std::io::Write;
+use csv::Writer;
+
+use crate::{FitnessOrder, Generation, Genotype, Individual, RunSummary};
+use crate::statistics::Statistics;
+
+/// every hook has a default that does nothing, so only the ones needed have to be written
+/// the generation is a read-only view of the population and its statistics
+pub trait Observer<T: Genotype + std::fmt::Debug> {
+    /// called once before the first generation, with the initial population
+    fn on_start(&mut self, _generation: &Generation<T>, _order: &FitnessOrder) -> Result<(), Box<dyn Error>> {
+        Ok(())
+    }
+
+    /// called after every generation
+    fn on_generation(&mut self, _generation: &Generation<T>, _order: &FitnessOrder) -> Result<(), Box<dyn Error>> {
+        Ok(())
+    }
+
+    /// called after `on_generation` whenever the best individual seen so far improves
+    fn on_improvement(&mut self, _generation: &Generation<T>, _best: &Individual<T>) -> Result<(), Box<dyn Error>> {
+        Ok(())
+    }
+
+    /// called once the run has stopped
+    fn on_termination(&mut self, _generation: &Generation<T>, _summary: &RunSummary<T>) -> Result<(), Box<dyn Error>> {
+        Ok(())
+    }
+
+    /// whether this observer reads the diversity statistics, so the engine has to track them
+    /// they compare every pair of genomes, so only ask for them if they're used
+    fn needs_diversity(&self) -> bool {
+        false
+    }
+}
+
+/// # Csv Observer
+/// writes one row per generation:
+/// `[prefix columns..., epoch, best_fitness, average_fitness, ...]`
+/// followed by the rest of the generation's statistics, see `Statistics::HEADERS`
+///
+/// the prefix columns identify the run, e.g. the crossover and mutation rates of a grid search,
+/// and can be changed between runs with `set_prefix` so many runs can share one file
+///
+/// the `unique` and `diversity` columns are empty unless something else is tracking diversity, or `with_diversity` is used
+pub struct CsvObserver<W: Write> {
+    writer: Writer<W>,
+    prefix: Vec<String>,
+    diversity: bool
+}
+
+impl<W: Write> CsvObserver<W> {
+    /// writes the header straight away
+    pub fn new(inner: W, prefix_headers: &[&str]) -> Result<Self, Box<dyn Error>> {
+        let mut writer = Writer::from_writer(inner);
+
+        let mut header: Vec<&str> = prefix_headers.to_vec();
+        header.push("epoch");
+        header.extend(Statistics::HEADERS);
+        writer.write_record(&header)?;
+
+        Ok(CsvObserver {
+            writer,
+            prefix: Vec::new(),
+            diversity: false
+        })
+    }
+
+    /// fill in the diversity columns, which are quadratic in the population size
+    pub fn with_diversity(mut self) -> Self {
+        self.diversity = true;
+        self
+    }
+
+    pub fn set_prefix(&mut self, prefix: Vec<String>) {
+        self.prefix = prefix;
+    }
+}
+
+impl<T: Genotype + std::fmt::Debug, W: Write> Observer<T> for CsvObserver<W> {
+    /// a run resumed from a checkpoint already has a history, which is written out first,
+    /// so the file has every generation no matter where the run was resumed from
+    fn on_start(&mut self, generation: &Generation<T>, _order: &FitnessOrder) -> Result<(), Box<dyn Error>> {
+        // the initial population isn't written for a fresh run either
+        for (epoch, statistics) in generation.get_history().iter().enumerate().skip(1) {
+            let mut record = self.prefix.clone();
+            record.push(epoch.to_string());
+            record.extend(statistics.to_record());
+            self.writer.write_record(&record)?;
+        }
+        Ok(())
+    }
+
+    fn on_generation(&mut self, generation: &Generation<T>, _order: &FitnessOrder) -> Result<(), Box<dyn Error>> {
+        let mut record = self.prefix.clone();
+        record.push(generation.get_generation().to_string());
+        record.extend(generation.get_statistics().to_record());
+        self.writer.write_record(&record)?;
+        Ok(())
+    }
+
+    fn on_termination(&mut self, _generation: &Generation<T>, _summary: &RunSummary<T>) -> Result<(), Box<dyn Error>> {
+        self.writer.flush()?;
+        Ok(())
+    }
+
+    fn needs_diversity(&self) -> bool {
+        self.diversity
+    }
+}
+
+/// # Progress Observer
+/// prints the best and average fitness to the console every `every` generations,
+/// along with the diversity if it's being tracked, and why the run stopped at the end
+pub struct ProgressObserver {
+    label: String,
+    every: usize
+}
+
+impl ProgressObserver {
+    pub fn new(label: &str, every: usize) -> Self {
+        ProgressObserver {
+            label: label.to_string(),
+            every: every.max(1)
+        }
+    }
+}
+
+impl<T: Genotype + std::fmt::Debug> Observer<T> for ProgressObserver {
+    fn on_generation(&mut self, generation: &Generation<T>, order: &FitnessOrder) -> Result<(), Box<dyn Error>> {
+        if generation.get_generation().is_multiple_of(self.every) {
+            let statistics = generation.get_statistics();
+            let diversity = match (statistics.diversity, statistics.unique) {
+                (Some(diversity), Some(unique)) => format!(", diversity: {:.2}, unique: {}", diversity, unique),
+                _ => String::new()
+            };
+            println!("{}: generation {}, best fitness: {}, average fitness: {}{}", self.label, generation.get_generation(), generation.get_best_fitness(order), generation.get_average_fitness(), diversity);
+        }
+        Ok(())
+    }
+
+    fn on_termination(&mut self, _generation: &Generation<T>, summary: &RunSummary<T>) -> Result<(), Box<dyn Error>> {
+        println!("{}: stopped by {:?} after {} generations ({} evaluations, {:.2?}), best fitness: {}", self.label, summary.reason, summary.generations, summary.evaluations, summary.elapsed, summary.best.fitness());
+        Ok(())
+    }
+}
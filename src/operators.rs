@@ -0,0 +1,305 @@
+/// # Adaptive Operator Selection
+///
+/// a pool of interchangeable operators, e.g. several mutations, one of which is picked each time one is needed
+/// the weights can be fixed, or adapted online from how much the children each operator makes improve on their parents
+///
+/// - probability matching, see adaptive strategy selection in differential evolution by GONG, FIALHO, CAI, LI
+/// - adaptive pursuit, see an adaptive pursuit strategy for allocating operator probabilities by THIERENS
+/// - upper confidence bound, see finite-time analysis of the multiarmed bandit problem by AUER, CESA-BIANCHI, FISCHER
+///
+/// the pool only picks an index, it's up to the problem to apply the matching operator,
+/// return its index from `Variation::crossover_with_operator` or `Variation::mutation_with_operator`,
+/// and pass the reward on from `Problem::feedback`, which is given the indices back in an `Origin`
+///
+/// the pool lives in a `Problem`, which is shared, so its state is behind a mutex
+/// to carry on adapting after a checkpoint, the problem saves the pool with `to_fields` from `Problem::save_state`,
+/// and restores it with `load_fields` from `Problem::load_state`
+use std::error::Error;
+use std::str::FromStr;
+use std::sync::Mutex;
+use rand::Rng;
+
+use crate::FitnessOrder;
+
+/// how the probability of picking each operator changes over a run
+#[derive(Debug, Clone, PartialEq)]
+pub enum Adaptation {
+    /// picked in proportion to the weights given, which never change
+    Fixed,
+    /// probability proportional to each operator's estimated reward,
+    /// with every operator kept at `p_min` or above so none are lost for good
+    /// the estimate moves `alpha` of the way towards every new reward
+    ProbabilityMatching { p_min: f64, alpha: f64 },
+    /// the operator with the best estimated reward has its probability pushed `beta` of the way to `1 - (k - 1) * p_min`,
+    /// and every other operator `beta` of the way to `p_min`
+    /// the estimate moves `alpha` of the way towards every new reward
+    AdaptivePursuit { p_min: f64, alpha: f64, beta: f64 },
+    /// always picks the operator with the highest mean reward plus `c * sqrt(2 ln n / n_i)`,
+    /// where n is the number of picks so far, and n_i the number of times that operator was picked
+    /// every operator is tried once first
+    Ucb { c: f64 }
+}
+
+/// how an operator has done over the run so far
+#[derive(Debug, Clone, PartialEq)]
+pub struct OperatorStatistics {
+    pub name: String,
+    /// times the operator was picked
+    pub chosen: usize,
+    /// children credited to the operator once evaluated
+    pub rewarded: usize,
+    /// children that were fitter than the fitter of their parents
+    pub successes: usize,
+    /// current chance of being picked, not used by `Adaptation::Ucb`
+    pub probability: f64,
+    /// current estimate of the operator's reward
+    pub quality: f64
+}
+
+#[derive(Debug, Clone)]
+struct PoolState {
+    probabilities: Vec<f64>,
+    quality: Vec<f64>,
+    total_reward: Vec<f64>,
+    chosen: Vec<usize>,
+    rewarded: Vec<usize>,
+    successes: Vec<usize>
+}
+
+#[derive(Debug)]
+pub struct OperatorPool {
+    names: Vec<String>,
+    adaptation: Adaptation,
+    state: Mutex<PoolState>
+}
+
+impl OperatorPool {
+    /// one operator per name, picked with the given weights to begin with
+    pub fn new(names: &[&str], weights: &[f64], adaptation: Adaptation) -> Self {
+        let total: f64 = weights.iter().sum();
+        let len = names.len();
+        let probabilities = if total > 0.0 && weights.len() == len {
+            weights.iter().map(|w| w / total).collect()
+        } else {
+            vec![1.0 / len as f64; len]
+        };
+
+        OperatorPool {
+            names: names.iter().map(|name| name.to_string()).collect(),
+            adaptation,
+            state: Mutex::new(PoolState {
+                probabilities,
+                quality: vec![0.0; len],
+                total_reward: vec![0.0; len],
+                chosen: vec![0; len],
+                rewarded: vec![0; len],
+                successes: vec![0; len]
+            })
+        }
+    }
+
+    /// the same operators and starting weights, adapted differently
+    pub fn with_adaptation(self, adaptation: Adaptation) -> Self {
+        OperatorPool { adaptation, ..self }
+    }
+
+    pub fn adaptation(&self) -> &Adaptation {
+        &self.adaptation
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    /// index of the operator to apply next
+    pub fn choose<R: Rng + ?Sized>(&self, rng: &mut R) -> usize {
+        let mut state = self.state.lock().unwrap();
+
+        let choice = match self.adaptation {
+            Adaptation::Ucb { c } => {
+                let picks: usize = state.chosen.iter().sum();
+                state.chosen.iter().position(|n| *n == 0).unwrap_or_else(|| {
+                    let bound = |i: usize| {
+                        let mean = state.total_reward[i] / state.rewarded[i].max(1) as f64;
+                        mean + c * (2.0 * (picks as f64).ln() / state.chosen[i] as f64).sqrt()
+                    };
+                    (0..self.len()).max_by(|a, b| bound(*a).total_cmp(&bound(*b))).unwrap()
+                })
+            },
+            _ => {
+                // one spin of the roulette wheel
+                let mut spin = rng.gen::<f64>();
+                state.probabilities.iter()
+                    .position(|p| {
+                        spin -= p;
+                        spin < 0.0
+                    })
+                    .unwrap_or(self.len() - 1)
+            }
+        };
+
+        state.chosen[choice] += 1;
+        choice
+    }
+
+    /// credit an operator with a child it made, once the child has been evaluated
+    /// the reward is the child's improvement on the fitter of its parents, relative to that parent, or 0 if it's no better
+    pub fn reward(&self, operator: usize, parent_fitness: f64, child_fitness: f64, order: &FitnessOrder) {
+        let improvement = if *order == FitnessOrder::Max {
+            child_fitness - parent_fitness
+        } else {
+            parent_fitness - child_fitness
+        };
+        let reward = if parent_fitness != 0.0 {
+            improvement / parent_fitness.abs()
+        } else {
+            improvement
+        }.max(0.0);
+
+        let mut state = self.state.lock().unwrap();
+        state.rewarded[operator] += 1;
+        state.total_reward[operator] += reward;
+        if improvement > 0.0 {
+            state.successes[operator] += 1;
+        }
+
+        let len = self.len() as f64;
+        match self.adaptation {
+            Adaptation::Fixed => {},
+            Adaptation::ProbabilityMatching { p_min, alpha } => {
+                state.quality[operator] += alpha * (reward - state.quality[operator]);
+
+                let total: f64 = state.quality.iter().sum();
+                if total > 0.0 {
+                    let scale = 1.0 - len * p_min;
+                    state.probabilities = state.quality.iter().map(|q| p_min + scale * q / total).collect();
+                }
+            },
+            Adaptation::AdaptivePursuit { p_min, alpha, beta } => {
+                state.quality[operator] += alpha * (reward - state.quality[operator]);
+
+                let best = (0..self.len())
+                    .max_by(|a, b| state.quality[*a].total_cmp(&state.quality[*b]))
+                    .unwrap();
+                let p_max = 1.0 - (len - 1.0) * p_min;
+                for i in 0..self.len() {
+                    let target = if i == best { p_max } else { p_min };
+                    state.probabilities[i] += beta * (target - state.probabilities[i]);
+                }
+            },
+            Adaptation::Ucb { .. } => {
+                state.quality[operator] = state.total_reward[operator] / state.rewarded[operator] as f64;
+            }
+        }
+    }
+
+    /// the adapted state as checkpoint fields, `[probabilities..., quality..., total reward..., chosen..., rewarded..., successes...]`
+    /// the operators and adaptation aren't included, they come from however the pool is built
+    pub fn to_fields(&self) -> Vec<String> {
+        let state = self.state.lock().unwrap();
+        let reals = [&state.probabilities, &state.quality, &state.total_reward];
+        let counts = [&state.chosen, &state.rewarded, &state.successes];
+
+        reals.iter().flat_map(|values| values.iter().map(|v| v.to_string()))
+            .chain(counts.iter().flat_map(|values| values.iter().map(|v| v.to_string())))
+            .collect()
+    }
+
+    /// restore the state written by `to_fields`, from a pool of the same operators
+    pub fn load_fields(&self, fields: &[String]) -> Result<(), Box<dyn Error>> {
+        let len = self.len();
+        if fields.len() != 6 * len {
+            return Err(format!("expected {} fields for {} operators, found {}", 6 * len, len, fields.len()).into());
+        }
+
+        fn parse<F>(fields: &[String]) -> Result<Vec<F>, Box<dyn Error>>
+        where
+            F: FromStr,
+            F::Err: Error + 'static
+        {
+            fields.iter().map(|field| Ok(field.parse()?)).collect()
+        }
+
+        // parsed in full before any of it is used, so a bad checkpoint leaves the pool as it was
+        let chunk = |idx: usize| &fields[idx * len..(idx + 1) * len];
+        let restored = PoolState {
+            probabilities: parse(chunk(0))?,
+            quality: parse(chunk(1))?,
+            total_reward: parse(chunk(2))?,
+            chosen: parse(chunk(3))?,
+            rewarded: parse(chunk(4))?,
+            successes: parse(chunk(5))?
+        };
+        *self.state.lock().unwrap() = restored;
+        Ok(())
+    }
+
+    /// per-operator counts, probabilities and reward estimates, in the order the operators were given
+    pub fn statistics(&self) -> Vec<OperatorStatistics> {
+        let state = self.state.lock().unwrap();
+        self.names.iter()
+            .enumerate()
+            .map(|(i, name)| OperatorStatistics {
+                name: name.clone(),
+                chosen: state.chosen[i],
+                rewarded: state.rewarded[i],
+                successes: state.successes[i],
+                probability: state.probabilities[i],
+                quality: state.quality[i]
+            })
+            .collect()
+    }
+}
+
+impl Clone for OperatorPool {
+    fn clone(&self) -> Self {
+        OperatorPool {
+            names: self.names.clone(),
+            adaptation: self.adaptation.clone(),
+            state: Mutex::new(self.state.lock().unwrap().clone())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    fn pool() -> OperatorPool {
+        OperatorPool::new(&["a", "b", "c"], &[1.0, 1.0, 2.0], Adaptation::AdaptivePursuit { p_min: 0.1, alpha: 0.3, beta: 0.2 })
+    }
+
+    #[test]
+    fn state_survives_a_round_trip() {
+        let mut rng = StdRng::seed_from_u64(3);
+        let adapted = pool();
+        for _ in 0..50 {
+            let operator = adapted.choose(&mut rng);
+            adapted.reward(operator, 10.0, 10.0 - operator as f64, &FitnessOrder::Min);
+        }
+
+        let restored = pool();
+        restored.load_fields(&adapted.to_fields()).unwrap();
+        assert_eq!(restored.statistics(), adapted.statistics());
+
+        // and it carries on choosing the same operators
+        let (mut rng_0, mut rng_1) = (StdRng::seed_from_u64(4), StdRng::seed_from_u64(4));
+        for _ in 0..20 {
+            assert_eq!(adapted.choose(&mut rng_0), restored.choose(&mut rng_1));
+        }
+    }
+
+    #[test]
+    fn rejects_state_of_another_pool() {
+        let other = OperatorPool::new(&["a", "b"], &[1.0, 1.0], Adaptation::Fixed);
+        let restored = pool();
+        assert!(restored.load_fields(&other.to_fields()).is_err());
+        assert_eq!(restored.statistics(), pool().statistics());
+    }
+}
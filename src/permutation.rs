@@ -0,0 +1,494 @@
+/// # Permutations
+///
+/// an ordering of a fixed set of elements, e.g. a tour of cities or a schedule of jobs,
+/// with the usual operators as public functions over any permutation, so other genomes can reuse them
+///
+/// crossover operators:
+/// - PMX
+/// - OX
+///
+/// see comparative analysis of c.o. by KUMAR, KUMAR, KARAMBIR
+///
+/// mutation operators:
+/// - RSM
+/// - PSM
+/// - SM
+///
+/// see mutation operators by ABDOUN, ABOUCHABAKA, TAJANI
+///
+/// the operators keep every element exactly once, so children are always valid permutations of their parents
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashSet;
+use std::error::Error;
+use std::hash::{Hash, Hasher};
+use bimap::BiMap;
+use rand::Rng;
+use rand::seq::SliceRandom;
+
+use crate::{Genotype, Problem, Variation};
+use crate::checkpoint::Checkpoint;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Permutation(Vec<usize>);
+
+impl Permutation {
+    /// every element should appear exactly once
+    pub fn new(order: Vec<usize>) -> Self {
+        Permutation(order)
+    }
+
+    pub fn order(&self) -> &[usize] {
+        &self.0
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// # edge distance
+    /// number of edges in this cycle that aren't in the other one
+    /// for orderings that wrap around, e.g. tours, where the start and direction don't matter,
+    /// so this is 0 for any rotation or reversal of the same cycle
+    pub fn edge_distance(&self, other: &Self) -> f64 {
+        // neighbours of each element in the other cycle, indexed by element
+        // elements are small, so this is much quicker than hashing every edge
+        let len = other.len();
+        let max_element = other.0.iter().max().copied().unwrap_or(0);
+        let mut neighbours = vec![[usize::MAX; 2]; max_element + 1];
+        for c in 0..len {
+            let element = other.0[c];
+            neighbours[element] = [other.0[(c + len - 1) % len], other.0[(c + 1) % len]];
+        }
+
+        (0..self.len())
+            .filter(|c| {
+                let a = self.0[*c];
+                let b = self.0[(*c + 1) % self.len()];
+                neighbours.get(a).is_none_or(|n| !n.contains(&b))
+            })
+            .count() as f64
+    }
+
+    /// # canonical cycle
+    /// hash of the cycle read from its lowest element, towards whichever neighbour of it is lower,
+    /// so that every rotation and reversal of a cycle has the same hash, to go with `edge_distance`
+    pub fn cyclic_hash(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        let len = self.len();
+        if len == 0 {
+            return hasher.finish();
+        }
+
+        let start = (0..len).min_by_key(|c| self.0[*c]).unwrap();
+        let forwards = self.0[(start + 1) % len] <= self.0[(start + len - 1) % len];
+
+        for step in 0..len {
+            let c = if forwards { (start + step) % len } else { (start + len - step) % len };
+            self.0[c].hash(&mut hasher);
+        }
+        hasher.finish()
+    }
+}
+
+impl Genotype for Permutation {
+    /// number of positions holding different elements, as for orderings like schedules it's the position that matters
+    /// see `edge_distance` for cycles
+    fn distance(&self, other: &Self) -> f64 {
+        self.0.iter()
+            .zip(other.0.iter())
+            .filter(|(a, b)| **a != **b)
+            .count() as f64
+    }
+
+    /// hash of the order, as only identical orders are at distance 0
+    fn canonical_hash(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.0.hash(&mut hasher);
+        hasher.finish()
+    }
+}
+
+impl Checkpoint for Permutation {
+    /// one field per element, in order
+    fn to_fields(&self) -> Vec<String> {
+        self.0.iter().map(|element| element.to_string()).collect()
+    }
+
+    fn from_fields(fields: &[&str]) -> Result<Self, Box<dyn Error>> {
+        Ok(Permutation(fields.iter().map(|element| element.parse()).collect::<Result<_, _>>()?))
+    }
+}
+
+/// how two parents are recombined
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Crossover {
+    /// see `partially_mapped_crossover`
+    PartiallyMapped,
+    /// see `order_crossover`
+    Order
+}
+
+impl Crossover {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Crossover::PartiallyMapped => "partially mapped",
+            Crossover::Order => "order"
+        }
+    }
+
+    pub fn apply<R: Rng + ?Sized>(&self, x: &Permutation, y: &Permutation, rng: &mut R) -> (Permutation, Permutation) {
+        let (child_0, child_1) = match self {
+            Crossover::PartiallyMapped => partially_mapped_crossover(&x.0, &y.0, rng),
+            Crossover::Order => order_crossover(&x.0, &y.0, rng)
+        };
+        (Permutation(child_0), Permutation(child_1))
+    }
+}
+
+/// how a permutation is changed, once it's been decided that it will be
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Mutation {
+    /// see `swap_mutation`
+    Swap,
+    /// see `reverse_sequence_mutation`
+    ReverseSequence,
+    /// see `partial_shuffle_mutation`
+    PartialShuffle
+}
+
+impl Mutation {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Mutation::Swap => "swap",
+            Mutation::ReverseSequence => "reverse sequence",
+            Mutation::PartialShuffle => "partial shuffle"
+        }
+    }
+
+    /// mutate in place
+    pub fn apply<R: Rng + ?Sized>(&self, permutation: &mut Permutation, rng: &mut R) {
+        match self {
+            Mutation::Swap => swap_mutation(&mut permutation.0, rng),
+            Mutation::ReverseSequence => reverse_sequence_mutation(&mut permutation.0, rng),
+            Mutation::PartialShuffle => partial_shuffle_mutation(&mut permutation.0, rng)
+        }
+    }
+}
+
+/// # Permutation Fitness
+/// the only part of an ordering problem that differs between problems
+pub trait PermutationFitness: Sync {
+    fn fitness(&self, order: &[usize]) -> f64;
+}
+
+/// # Permutation Problem
+/// random orderings of `elements`, recombined and mutated with the operators chosen,
+/// and evaluated with `fitness`
+#[derive(Debug, Clone)]
+pub struct PermutationProblem<F: PermutationFitness> {
+    /// every element, in any order, shuffled to make a random permutation
+    pub elements: Vec<usize>,
+    pub crossover: Crossover,
+    pub mutation: Mutation,
+    /// chance of a pair of parents being crossed at all
+    pub crossover_rate: f64,
+    /// chance of a permutation being mutated at all
+    pub mutation_rate: f64,
+    pub fitness: F
+}
+
+impl<F: PermutationFitness> PermutationProblem<F> {
+    /// pmx and reverse sequence mutation, crossing 90% of pairs and mutating 10% of children
+    pub fn new(elements: Vec<usize>, fitness: F) -> Self {
+        PermutationProblem {
+            elements,
+            crossover: Crossover::PartiallyMapped,
+            mutation: Mutation::ReverseSequence,
+            crossover_rate: 0.9,
+            mutation_rate: 0.1,
+            fitness
+        }
+    }
+}
+
+impl<F: PermutationFitness> Variation for PermutationProblem<F> {
+    type Genome = Permutation;
+
+    /// a uniformly random permutation, using a fisher-yates shuffle
+    fn random<R: Rng + ?Sized>(&self, rng: &mut R) -> Permutation {
+        let mut order = self.elements.clone();
+        order.shuffle(rng);
+        Permutation(order)
+    }
+
+    fn crossover<R: Rng + ?Sized>(&self, x: &Permutation, y: &Permutation, rng: &mut R) -> (Permutation, Permutation) {
+        if rng.gen::<f64>() < self.crossover_rate {
+            return self.crossover.apply(x, y, rng);
+        }
+        (x.clone(), y.clone())
+    }
+
+    fn mutation<R: Rng + ?Sized>(&self, genome: &Permutation, rng: &mut R) -> Permutation {
+        let mut next = genome.clone();
+        if rng.gen::<f64>() < self.mutation_rate {
+            self.mutation.apply(&mut next, rng);
+        }
+        next
+    }
+}
+
+impl<F: PermutationFitness> Problem for PermutationProblem<F> {
+    fn fitness(&self, genome: &Permutation) -> f64 {
+        self.fitness.fitness(&genome.0)
+    }
+}
+
+// --------------------
+// Mutation Operators
+// --------------------
+
+/// # Partial Shuffle Mutation (PSM)
+/// shuffle a section of the genes in the genotype
+/// this one is far more destructive
+/// needs at least 2 elements
+pub fn partial_shuffle_mutation<R: Rng + ?Sized>(order: &mut [usize], rng: &mut R) {
+    let i = rng.gen_range(0..order.len() - 1);
+    let j = rng.gen_range(i..order.len());
+
+    if i != j {
+        let slice = &mut order[i..=j];
+        slice.shuffle(rng);
+    }
+}
+
+/// # Reverse Sequence Mutation (RSM)
+/// we take a sequence between positions i and j, with i<j
+/// the gene order of this sequence is then reversed
+/// needs at least 2 elements
+pub fn reverse_sequence_mutation<R: Rng + ?Sized>(order: &mut [usize], rng: &mut R) {
+    let i = rng.gen_range(0..order.len() - 1);
+    let j = rng.gen_range(i..order.len());
+
+    if i != j {
+        let slice = &mut order[i..=j];
+        slice.reverse();
+    }
+}
+
+/// # Swap Mutation (SM)
+/// does what it says on the tin
+/// fewer than 2 elements are left as they are
+pub fn swap_mutation<R: Rng + ?Sized>(order: &mut [usize], rng: &mut R) {
+    if order.len() < 2 {
+        return;
+    }
+
+    let i = rng.gen_range(0..order.len());
+    let j = rng.gen_range(0..order.len());
+
+    if i != j {
+        order.swap(i, j);
+    }
+}
+
+// --------------------
+// Crossover Operators
+// --------------------
+
+/// # Partially Mapped Crossover (PMX)
+/// let parent_0 consisting of genotypes a_0...a_n and parent_1 with genotypes b_0...b_n
+/// let empty genomes child_0 and child_1 of length n
+/// we take two random numbers in the range [0, n], i and j, with i < j
+/// elements a_i...a_j are placed in child_1 in the same locations, and b_i...b_j similarly in child_0
+/// we then create a bijective mapping as follows
+/// f(a_k) = b_k, and f(b_k) = a_k
+/// we fill the rest of child_1 from parent_1, and the rest of child_0 from parent_0,
+/// passing any element already in the copied section through the mapping until it isn't, ensuring that we have no invalid tours
+/// needs at least 2 elements
+pub fn partially_mapped_crossover<R: Rng + ?Sized>(parent_0: &[usize], parent_1: &[usize], rng: &mut R) -> (Vec<usize>, Vec<usize>) {
+    let length = parent_0.len();
+
+    let i = rng.gen_range(0..length - 1);
+    let j = rng.gen_range(i+1..length);
+
+    let mut child_0 = parent_0.to_vec();
+    let mut child_1 = parent_1.to_vec();
+
+    let mut mapping = BiMap::new();
+
+    // copy middle sections from parents
+    // and create mapping between elements
+    for idx in i..=j {
+        child_1[idx] = parent_0[idx];
+        child_0[idx] = parent_1[idx];
+
+        mapping.insert(parent_0[idx], parent_1[idx]);
+    }
+
+    // fill the left and right sections from the other parent, through the mapping
+    for idx in (0..i).chain(j + 1..length) {
+        // parent_1 and child_1
+        // an element already in child_1's middle is a left value, so follow it to the right
+        // until we reach one that isn't, which can't be anywhere else in child_1
+        let mut value = parent_1[idx];
+        while let Some(mapped) = mapping.get_by_left(&value) {
+            value = *mapped;
+        }
+        child_1[idx] = value;
+
+        // parent_0 and child_0, the same the other way around
+        let mut value = parent_0[idx];
+        while let Some(mapped) = mapping.get_by_right(&value) {
+            value = *mapped;
+        }
+        child_0[idx] = value;
+    }
+
+    (child_0, child_1)
+}
+
+/// # Order Crossover (OX)
+/// Select k random positions from parent_0 and copy them into child_0
+/// iterate through parent_1 and copy each currently unused index into the next empty space in child_0
+/// repeat this process using the n-k positions not chosen from parent_0
+pub fn order_crossover<R: Rng + ?Sized>(parent_0: &[usize], parent_1: &[usize], rng: &mut R) -> (Vec<usize>, Vec<usize>) {
+    let length = parent_0.len();
+    let k = rng.gen_range(0..length);
+
+    let mut indices = HashSet::new();
+
+    // set values in child arrays to usize::MAX to represent "empty" indices
+    // it's unlikely that we ever need to represent that many cities, otherwise i'd use an option
+    let mut child_0 = vec![usize::MAX; length];
+    let mut child_1 = vec![usize::MAX; length];
+
+    // generate k random positions
+    while indices.len() < k {
+        indices.insert(rng.gen_range(0..length));
+    }
+
+    // place the k selected values into child_0, and the other n-k values into child_1
+    for idx in 0..length {
+        if indices.contains(&idx) {
+            child_0[idx] = parent_0[idx];
+        } else {
+            child_1[idx] = parent_0[idx];
+        }
+    }
+
+    // fill out the empty spaces
+    let mut child_0_idx = 0;
+    let mut child_1_idx = 0;
+    for value in parent_1 {
+        // insert the next unused index
+        if !child_0.contains(value) {
+            // find the next empty space
+            while child_0[child_0_idx] != usize::MAX {
+                child_0_idx += 1;
+            }
+            child_0[child_0_idx] = *value;
+            child_0_idx += 1;
+        }
+        // repeat for child_1
+        // this could possibly be an else instead of another if but i'm not 100% sure
+        if !child_1.contains(value) {
+            // find the next empty space
+            while child_1[child_1_idx] != usize::MAX {
+                child_1_idx += 1;
+            }
+            child_1[child_1_idx] = *value;
+            child_1_idx += 1;
+        }
+    }
+
+    (child_0, child_1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    fn is_permutation(order: &[usize], length: usize) -> bool {
+        let mut sorted = order.to_vec();
+        sorted.sort_unstable();
+        sorted == (0..length).collect::<Vec<_>>()
+    }
+
+    fn parents(rng: &mut StdRng) -> (Vec<usize>, Vec<usize>) {
+        let mut parent_0: Vec<usize> = (0..10).collect();
+        let mut parent_1 = parent_0.clone();
+        parent_0.shuffle(rng);
+        parent_1.shuffle(rng);
+        (parent_0, parent_1)
+    }
+
+    #[test]
+    fn pmx_recombines_into_valid_permutations() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let mut recombined = 0;
+
+        for _ in 0..1000 {
+            let (parent_0, parent_1) = parents(&mut rng);
+            let (child_0, child_1) = partially_mapped_crossover(&parent_0, &parent_1, &mut rng);
+
+            assert!(is_permutation(&child_0, 10));
+            assert!(is_permutation(&child_1, 10));
+            if child_0 != parent_0 && child_0 != parent_1 && child_1 != parent_0 && child_1 != parent_1 {
+                recombined += 1;
+            }
+        }
+
+        // the odd copy is fine, e.g. when the copied section is the whole permutation
+        assert!(recombined > 800, "only {} of 1000 crossovers recombined", recombined);
+    }
+
+    #[test]
+    fn ox_gives_valid_permutations() {
+        let mut rng = StdRng::seed_from_u64(3);
+
+        for _ in 0..1000 {
+            let (parent_0, parent_1) = parents(&mut rng);
+            let (child_0, child_1) = order_crossover(&parent_0, &parent_1, &mut rng);
+
+            assert!(is_permutation(&child_0, 10));
+            assert!(is_permutation(&child_1, 10));
+        }
+    }
+
+    #[test]
+    fn mutations_give_valid_permutations() {
+        let mut rng = StdRng::seed_from_u64(4);
+
+        for mutation in [Mutation::Swap, Mutation::ReverseSequence, Mutation::PartialShuffle] {
+            let mut permutation = Permutation::new((0..10).collect());
+            for _ in 0..1000 {
+                mutation.apply(&mut permutation, &mut rng);
+                assert!(is_permutation(permutation.order(), 10), "{} broke the permutation", mutation.name());
+            }
+        }
+
+        for length in [0, 1] {
+            let mut order: Vec<usize> = (0..length).collect();
+            swap_mutation(&mut order, &mut rng);
+            assert!(is_permutation(&order, length));
+        }
+    }
+
+    #[test]
+    fn edge_distance_ignores_rotation_and_reversal() {
+        let tour = Permutation::new(vec![0, 1, 2, 3, 4]);
+        let rotated = Permutation::new(vec![3, 4, 0, 1, 2]);
+        let reversed = Permutation::new(vec![4, 3, 2, 1, 0]);
+
+        assert_eq!(tour.edge_distance(&rotated), 0.0);
+        assert_eq!(tour.edge_distance(&reversed), 0.0);
+        assert_eq!(tour.cyclic_hash(), rotated.cyclic_hash());
+        assert_eq!(tour.cyclic_hash(), reversed.cyclic_hash());
+    }
+}
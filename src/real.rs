@@ -0,0 +1,384 @@
+/// # Real-Valued Vectors
+///
+/// a vector of reals, each within its own bounds, with the usual continuous operators
+///
+/// - simulated binary crossover, see simulated binary crossover for continuous search space by DEB, AGRAWAL
+/// - blend crossover, see real-coded genetic algorithms and interval-schemata by ESHELMAN, SCHAFFER
+/// - whole arithmetic crossover
+/// - gaussian mutation
+/// - polynomial mutation, see a combined genetic adaptive search for engineering design by DEB, GOYAL
+///
+/// values pushed out of bounds by an operator are brought back in by the problem's `BoundHandling`
+use std::collections::hash_map::DefaultHasher;
+use std::error::Error;
+use std::hash::{Hash, Hasher};
+use rand::Rng;
+
+use crate::{gaussian, Genotype, Problem, Variation};
+use crate::checkpoint::Checkpoint;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RealVector(Vec<f64>);
+
+impl RealVector {
+    pub fn new(values: Vec<f64>) -> Self {
+        RealVector(values)
+    }
+
+    pub fn values(&self) -> &[f64] {
+        &self.0
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl Genotype for RealVector {
+    /// euclidean distance
+    fn distance(&self, other: &Self) -> f64 {
+        self.0.iter()
+            .zip(other.0.iter())
+            .map(|(a, b)| (a - b).powi(2))
+            .sum::<f64>()
+            .sqrt()
+    }
+
+    /// hash of the exact values, as only identical vectors are at distance 0
+    fn canonical_hash(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        for value in &self.0 {
+            // adding 0 turns -0 into 0, which is at distance 0 from it
+            (value + 0.0).to_bits().hash(&mut hasher);
+        }
+        hasher.finish()
+    }
+}
+
+impl Checkpoint for RealVector {
+    /// one field per value, written so it reads back exactly
+    fn to_fields(&self) -> Vec<String> {
+        self.0.iter().map(|value| value.to_string()).collect()
+    }
+
+    fn from_fields(fields: &[&str]) -> Result<Self, Box<dyn Error>> {
+        Ok(RealVector(fields.iter().map(|value| value.parse()).collect::<Result<_, _>>()?))
+    }
+}
+
+/// how two parents are recombined
+#[derive(Debug, Clone, PartialEq)]
+pub enum Crossover {
+    /// simulated binary crossover, each value crossed with probability 0.5
+    /// a larger `eta` keeps children closer to their parents, 2 to 20 is typical
+    Sbx { eta: f64 },
+    /// blx-α, each child value drawn uniformly from the range between the parents' values,
+    /// widened by `alpha` of its width on either side
+    Blx { alpha: f64 },
+    /// whole arithmetic, children are the weighted averages `λx + (1 - λ)y` and `(1 - λ)x + λy`,
+    /// with λ drawn uniformly once per crossover
+    Arithmetic
+}
+
+/// how each value is changed, once it's been decided that it will be
+#[derive(Debug, Clone, PartialEq)]
+pub enum Mutation {
+    /// add normally distributed noise, with standard deviation `sigma` times the width of the value's bounds
+    Gaussian { sigma: f64 },
+    /// polynomial mutation, a larger `eta` keeps the change smaller, 20 is typical
+    Polynomial { eta: f64 }
+}
+
+/// what happens to a value an operator has pushed out of its bounds
+#[derive(Debug, Clone, PartialEq)]
+pub enum BoundHandling {
+    /// moved to the nearest bound
+    Clamp,
+    /// mirrored back in off the bound it crossed, as many times as it takes
+    Reflect,
+    /// replaced with a value drawn uniformly from the bounds
+    Resample
+}
+
+impl BoundHandling {
+    fn apply<R: Rng + ?Sized>(&self, value: f64, (lower, upper): (f64, f64), rng: &mut R) -> f64 {
+        if (lower..=upper).contains(&value) {
+            return value;
+        }
+
+        match self {
+            BoundHandling::Clamp => value.clamp(lower, upper),
+            BoundHandling::Reflect => {
+                let width = upper - lower;
+                if width <= 0.0 || !value.is_finite() {
+                    return value.clamp(lower, upper);
+                }
+                // going back and forth between the bounds repeats every two widths
+                let offset = (value - lower).rem_euclid(2.0 * width);
+                if offset <= width {
+                    lower + offset
+                } else {
+                    upper - (offset - width)
+                }
+            },
+            BoundHandling::Resample => rng.gen_range(lower..=upper)
+        }
+    }
+}
+
+/// # Real Fitness
+/// the only part of a real-valued problem that differs between problems
+pub trait RealFitness: Sync {
+    fn fitness(&self, values: &[f64]) -> f64;
+}
+
+/// # Real Vector Problem
+/// random vectors drawn uniformly from `bounds`, one `(lower, upper)` per dimension,
+/// recombined and mutated with the operators chosen, and evaluated with `fitness`
+/// nothing here assumes a direction, though most continuous problems are run with `FitnessOrder::Min`
+#[derive(Debug, Clone)]
+pub struct RealVectorProblem<F: RealFitness> {
+    bounds: Vec<(f64, f64)>,
+    crossover: Crossover,
+    pub mutation: Mutation,
+    pub bound_handling: BoundHandling,
+    /// chance of a pair of parents being crossed at all
+    pub crossover_rate: f64,
+    /// chance of each value being mutated
+    pub mutation_rate: f64,
+    pub fitness: F
+}
+
+impl<F: RealFitness> RealVectorProblem<F> {
+    /// sbx and polynomial mutation with eta 20, clamped,
+    /// crossing 90% of pairs, and mutating one value per vector on average
+    /// every bound must be finite, with its lower no greater than its upper, as values are drawn from between them
+    pub fn new(bounds: Vec<(f64, f64)>, fitness: F) -> Result<Self, Box<dyn Error>> {
+        if let Some((lower, upper)) = bounds.iter().find(|(lower, upper)| !(lower.is_finite() && upper.is_finite() && lower <= upper)) {
+            return Err(format!("bounds must be finite with lower <= upper, not ({}, {})", lower, upper).into());
+        }
+
+        let mutation_rate = 1.0 / bounds.len().max(1) as f64;
+        Ok(RealVectorProblem {
+            bounds,
+            crossover: Crossover::Sbx { eta: 20.0 },
+            mutation: Mutation::Polynomial { eta: 20.0 },
+            bound_handling: BoundHandling::Clamp,
+            crossover_rate: 0.9,
+            mutation_rate,
+            fitness
+        })
+    }
+
+    /// the same bounds for every one of `dimensions`
+    pub fn uniform(dimensions: usize, bounds: (f64, f64), fitness: F) -> Result<Self, Box<dyn Error>> {
+        Self::new(vec![bounds; dimensions], fitness)
+    }
+
+    /// use a different crossover, blx-α needs a finite, non-negative `alpha`
+    pub fn with_crossover(mut self, crossover: Crossover) -> Result<Self, Box<dyn Error>> {
+        if let Crossover::Blx { alpha } = crossover {
+            if !(alpha.is_finite() && alpha >= 0.0) {
+                return Err(format!("blx alpha must be finite and non-negative, not {}", alpha).into());
+            }
+        }
+        self.crossover = crossover;
+        Ok(self)
+    }
+
+    pub fn get_bounds(&self) -> &[(f64, f64)] {
+        &self.bounds
+    }
+
+    pub fn get_crossover(&self) -> &Crossover {
+        &self.crossover
+    }
+
+    pub fn dimensions(&self) -> usize {
+        self.bounds.len()
+    }
+}
+
+impl<F: RealFitness> Variation for RealVectorProblem<F> {
+    type Genome = RealVector;
+
+    fn random<R: Rng + ?Sized>(&self, rng: &mut R) -> RealVector {
+        RealVector(self.bounds.iter()
+            .map(|(lower, upper)| rng.gen_range(*lower..=*upper))
+            .collect())
+    }
+
+    fn crossover<R: Rng + ?Sized>(&self, x: &RealVector, y: &RealVector, rng: &mut R) -> (RealVector, RealVector) {
+        let mut child_0 = x.clone();
+        let mut child_1 = y.clone();
+
+        if rng.gen::<f64>() >= self.crossover_rate {
+            return (child_0, child_1);
+        }
+
+        match self.crossover {
+            Crossover::Sbx { eta } => {
+                for idx in 0..self.bounds.len() {
+                    // identical values would only give the same values back
+                    if !rng.gen_bool(0.5) || (x.0[idx] - y.0[idx]).abs() < f64::EPSILON {
+                        continue;
+                    }
+
+                    let u: f64 = rng.gen();
+                    let beta = if u <= 0.5 {
+                        (2.0 * u).powf(1.0 / (eta + 1.0))
+                    } else {
+                        (1.0 / (2.0 * (1.0 - u))).powf(1.0 / (eta + 1.0))
+                    };
+
+                    child_0.0[idx] = 0.5 * ((1.0 + beta) * x.0[idx] + (1.0 - beta) * y.0[idx]);
+                    child_1.0[idx] = 0.5 * ((1.0 - beta) * x.0[idx] + (1.0 + beta) * y.0[idx]);
+                }
+            },
+            Crossover::Blx { alpha } => {
+                for idx in 0..self.bounds.len() {
+                    let low = x.0[idx].min(y.0[idx]);
+                    let high = x.0[idx].max(y.0[idx]);
+                    let spread = alpha * (high - low);
+
+                    child_0.0[idx] = rng.gen_range(low - spread..=high + spread);
+                    child_1.0[idx] = rng.gen_range(low - spread..=high + spread);
+                }
+            },
+            Crossover::Arithmetic => {
+                let lambda: f64 = rng.gen();
+                for idx in 0..self.bounds.len() {
+                    child_0.0[idx] = lambda * x.0[idx] + (1.0 - lambda) * y.0[idx];
+                    child_1.0[idx] = (1.0 - lambda) * x.0[idx] + lambda * y.0[idx];
+                }
+            }
+        }
+
+        for (idx, bounds) in self.bounds.iter().enumerate() {
+            child_0.0[idx] = self.bound_handling.apply(child_0.0[idx], *bounds, rng);
+            child_1.0[idx] = self.bound_handling.apply(child_1.0[idx], *bounds, rng);
+        }
+
+        (child_0, child_1)
+    }
+
+    fn mutation<R: Rng + ?Sized>(&self, genome: &RealVector, rng: &mut R) -> RealVector {
+        let mut next = genome.clone();
+
+        for (value, (lower, upper)) in next.0.iter_mut().zip(self.bounds.iter()) {
+            // chance of mutation
+            if rng.gen::<f64>() >= self.mutation_rate {
+                continue;
+            }
+
+            let width = upper - lower;
+            let mutated = match self.mutation {
+                Mutation::Gaussian { sigma } => *value + sigma * width * gaussian(rng),
+                Mutation::Polynomial { eta } => {
+                    let u: f64 = rng.gen();
+                    let delta = if u < 0.5 {
+                        (2.0 * u).powf(1.0 / (eta + 1.0)) - 1.0
+                    } else {
+                        1.0 - (2.0 * (1.0 - u)).powf(1.0 / (eta + 1.0))
+                    };
+                    *value + delta * width
+                }
+            };
+
+            *value = self.bound_handling.apply(mutated, (*lower, *upper), rng);
+        }
+
+        next
+    }
+}
+
+impl<F: RealFitness> Problem for RealVectorProblem<F> {
+    fn fitness(&self, genome: &RealVector) -> f64 {
+        self.fitness.fitness(&genome.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    #[derive(Clone)]
+    struct Sphere;
+
+    impl RealFitness for Sphere {
+        fn fitness(&self, values: &[f64]) -> f64 {
+            values.iter().map(|value| value * value).sum()
+        }
+    }
+
+    fn in_bounds(problem: &RealVectorProblem<Sphere>, genome: &RealVector) -> bool {
+        genome.values().iter()
+            .zip(problem.get_bounds())
+            .all(|(value, (lower, upper))| (*lower..=*upper).contains(value))
+    }
+
+    #[test]
+    fn children_stay_in_bounds() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let crossovers = [Crossover::Sbx { eta: 2.0 }, Crossover::Blx { alpha: 0.5 }, Crossover::Arithmetic];
+        let mutations = [Mutation::Gaussian { sigma: 0.5 }, Mutation::Polynomial { eta: 2.0 }];
+
+        for crossover in crossovers {
+            for mutation in mutations.iter().cloned() {
+                let mut problem = RealVectorProblem::new(vec![(-1.0, 1.0), (0.0, 10.0), (5.0, 5.0)], Sphere).unwrap()
+                    .with_crossover(crossover.clone()).unwrap();
+                problem.mutation = mutation;
+                problem.crossover_rate = 1.0;
+                problem.mutation_rate = 1.0;
+
+                for _ in 0..200 {
+                    let x = problem.random(&mut rng);
+                    let y = problem.random(&mut rng);
+                    let (child_0, child_1) = problem.crossover(&x, &y, &mut rng);
+                    assert!(in_bounds(&problem, &child_0) && in_bounds(&problem, &child_1), "{:?}", problem.get_crossover());
+                    assert!(in_bounds(&problem, &problem.mutation(&child_0, &mut rng)), "{:?}", problem.mutation);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn bound_handling() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let bounds = (-1.0, 3.0);
+        let width = 4.0;
+
+        assert_eq!(BoundHandling::Clamp.apply(3.5, bounds, &mut rng), 3.0);
+        assert_eq!(BoundHandling::Clamp.apply(-7.0, bounds, &mut rng), -1.0);
+        assert_eq!(BoundHandling::Reflect.apply(3.0 + 0.25 * width, bounds, &mut rng), 3.0 - 0.25 * width);
+        assert_eq!(BoundHandling::Reflect.apply(-1.0 - 0.25 * width, bounds, &mut rng), -1.0 + 0.25 * width);
+        // far enough out to bounce off both bounds
+        assert_eq!(BoundHandling::Reflect.apply(3.0 + 1.25 * width, bounds, &mut rng), 3.0 - 0.75 * width);
+
+        for _ in 0..100 {
+            let value = BoundHandling::Resample.apply(10.0, bounds, &mut rng);
+            assert!((-1.0..=3.0).contains(&value));
+        }
+        // in bounds is left alone
+        assert_eq!(BoundHandling::Resample.apply(0.5, bounds, &mut rng), 0.5);
+    }
+
+    #[test]
+    fn constructors_reject_bad_parameters() {
+        assert!(RealVectorProblem::new(vec![(1.0, -1.0)], Sphere).is_err());
+        assert!(RealVectorProblem::new(vec![(0.0, 1.0), (f64::NEG_INFINITY, 1.0)], Sphere).is_err());
+        assert!(RealVectorProblem::uniform(2, (0.0, f64::NAN), Sphere).is_err());
+        assert!(RealVectorProblem::uniform(2, (1.0, 1.0), Sphere).is_ok());
+
+        let problem = RealVectorProblem::uniform(2, (0.0, 1.0), Sphere).unwrap();
+        assert!(problem.clone().with_crossover(Crossover::Blx { alpha: -0.1 }).is_err());
+        assert!(problem.clone().with_crossover(Crossover::Blx { alpha: f64::INFINITY }).is_err());
+        assert!(problem.with_crossover(Crossover::Blx { alpha: 0.0 }).is_ok());
+    }
+}
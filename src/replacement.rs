@@ -0,0 +1,179 @@
+/// # Replacement Schemes
+///
+/// how the offspring of a generation are merged back into the population
+/// - generational, with a configurable number of elites
+/// - (μ+λ) and (μ,λ)
+/// - replace worst
+/// - deterministic crowding
+/// - restricted tournament, see finding multimodal solutions using restricted tournament selection by HARIK
+use rand::Rng;
+use rand::seq::{index, SliceRandom};
+use rayon::prelude::*;
+
+use crate::{FitnessOrder, Generation, Genotype, Individual, Problem};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Replacement {
+    /// offspring replace the whole population, apart from the best `elites` which are kept unchanged
+    Generational { elites: usize },
+    /// (μ+λ): `offspring` children are bred, and the best μ of parents and children survive
+    PlusLambda { offspring: usize },
+    /// (μ,λ): `offspring` children are bred, and only the best μ of the children survive
+    /// λ should be at least μ, otherwise the best parents are kept to fill the gap
+    CommaLambda { offspring: usize },
+    /// `offspring` children are bred, and replace the worst members of the population
+    ReplaceWorst { offspring: usize },
+    /// the population is paired up at random, and each child only replaces
+    /// the more similar of its two parents, if it is at least as fit
+    /// parents aren't chosen by the selection strategy in this scheme
+    DeterministicCrowding,
+    /// a whole population of children is bred, and each in turn is compared with
+    /// the most similar of `window` members drawn at random, replacing it if at least as fit
+    RestrictedTournament { window: usize }
+}
+
+impl Default for Replacement {
+    /// the original scheme, generational with the top 2 kept
+    fn default() -> Self {
+        Replacement::Generational { elites: 2 }
+    }
+}
+
+impl Replacement {
+    /// how many children need to be bred each generation for a population of `size`
+    pub fn offspring_count(&self, size: usize) -> usize {
+        match self {
+            Replacement::Generational { elites } => size.saturating_sub(*elites),
+            Replacement::PlusLambda { offspring }
+                | Replacement::CommaLambda { offspring } => *offspring,
+            Replacement::ReplaceWorst { offspring } => (*offspring).min(size),
+            Replacement::DeterministicCrowding
+                | Replacement::RestrictedTournament { .. } => size
+        }
+    }
+
+    /// merge `offspring` into `population`, which must already be sorted fittest first
+    /// leaves the population sorted fittest first, apart from with deterministic crowding and restricted tournaments
+    pub(crate) fn replace<T: Genotype + Send, R: Rng + ?Sized>(&self, population: &mut Vec<Individual<T>>, mut offspring: Vec<Individual<T>>, size: usize, order: &FitnessOrder, rng: &mut R) {
+        match self {
+            Replacement::Generational { elites } => {
+                // stronger elitism - keep the best n solutions unchanged
+                // these keep their cached fitness, so aren't evaluated again
+                population.truncate(*elites);
+                offspring.truncate(size.saturating_sub(*elites));
+                population.append(&mut offspring);
+            },
+            Replacement::PlusLambda { .. } => {
+                population.append(&mut offspring);
+                population.par_sort_by(|a, b| order.compare(a.fitness(), b.fitness()));
+                population.truncate(size);
+            },
+            Replacement::CommaLambda { .. } => {
+                offspring.par_sort_by(|a, b| order.compare(a.fitness(), b.fitness()));
+                offspring.truncate(size);
+
+                // not enough children to go around, so the best parents fill the gap
+                let shortfall = size - offspring.len();
+                population.truncate(shortfall);
+                offspring.append(population);
+                offspring.par_sort_by(|a, b| order.compare(a.fitness(), b.fitness()));
+                *population = offspring;
+            },
+            Replacement::ReplaceWorst { .. } => {
+                offspring.truncate(size);
+                population.truncate(size - offspring.len());
+                population.append(&mut offspring);
+                population.par_sort_by(|a, b| order.compare(a.fitness(), b.fitness()));
+            },
+            Replacement::DeterministicCrowding => {
+                unreachable!("deterministic crowding replaces parents as it breeds, see `deterministic_crowding`")
+            },
+            Replacement::RestrictedTournament { window } => {
+                // with nobody to compete against, the children just become the population
+                if population.is_empty() {
+                    offspring.truncate(size);
+                    *population = offspring;
+                    return;
+                }
+
+                let window = (*window).min(population.len()).max(1);
+                for child in offspring {
+                    // earlier children can be drawn, and replaced, by later ones
+                    let (closest, _) = index::sample(rng, population.len(), window)
+                        .into_iter()
+                        .map(|idx| (idx, child.genome().distance(population[idx].genome())))
+                        .min_by(|a, b| a.1.total_cmp(&b.1))
+                        .unwrap();
+
+                    if order.compare(child.fitness(), population[closest].fitness()).is_le() {
+                        population[closest] = child;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// # Deterministic Crowding
+/// pair up the whole population at random, breed each pair,
+/// then match each child to its closest parent
+/// (the pairing with the lowest total distance)
+/// a child replaces its parent if it is at least as fit
+pub(crate) fn deterministic_crowding<P, R>(gen: &mut Generation<P::Genome>, problem: &P, order: &FitnessOrder, rng: &mut R)
+where
+    P: Problem,
+    R: Rng + ?Sized
+{
+    let mut indices: Vec<usize> = (0..gen.population.len()).collect();
+    indices.shuffle(rng);
+
+    let (offspring, origins) = gen.breed(problem, &indices, rng);
+    let mut offspring = gen.evaluate(problem, offspring);
+    gen.apply_constraints(&mut offspring, order);
+    gen.feedback(problem, &indices, &offspring, &origins, order);
+
+    for (pair, children) in indices.chunks_exact(2).zip(offspring.chunks_exact(2)) {
+        let (p0, p1) = (pair[0], pair[1]);
+        let (c0, c1) = (&children[0], &children[1]);
+
+        let parallel = c0.genome().distance(gen.population[p0].genome())
+            + c1.genome().distance(gen.population[p1].genome());
+        let crossed = c0.genome().distance(gen.population[p1].genome())
+            + c1.genome().distance(gen.population[p0].genome());
+
+        let matches = if parallel <= crossed {
+            [(p0, c0), (p1, c1)]
+        } else {
+            [(p0, c1), (p1, c0)]
+        };
+
+        for (parent, child) in matches {
+            if order.compare(child.fitness(), gen.population[parent].fitness()).is_le() {
+                gen.population[parent] = child.clone();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+    use crate::Variation;
+    use crate::bitstring::{Bitstring, BitstringProblem};
+    use crate::test_util::OneMax;
+
+    #[test]
+    fn restricted_tournament_fills_an_empty_population() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let problem: BitstringProblem<OneMax, Bitstring> = BitstringProblem::new(10, OneMax);
+        let offspring: Vec<Individual<Bitstring>> = (0..4)
+            .map(|_| Individual::new(&problem, problem.random(&mut rng)))
+            .collect();
+
+        let mut population = Vec::new();
+        Replacement::RestrictedTournament { window: 3 }.replace(&mut population, offspring, 3, &FitnessOrder::Max, &mut rng);
+        assert_eq!(population.len(), 3);
+    }
+}
@@ -0,0 +1,331 @@
+/// # Selection Strategies
+///
+/// all strategies pick parents by index into the population,
+/// and honour the `FitnessOrder` so they work for both max and min problems
+///
+/// - k-way tournament, with and without replacement
+/// - fitness-proportionate (roulette wheel)
+/// - stochastic universal sampling
+/// - linear and exponential rank
+/// - truncation
+/// - boltzmann
+///
+/// strategies only look at fitness, so an adjustment that looks at the genomes can go in front of any of them,
+/// see `niching::Adjustment`
+/// an empty population gives no parents, whatever the count asked for
+use std::cmp::Ordering;
+use std::error::Error;
+use rand::Rng;
+use rand::distributions::{Distribution, WeightedIndex};
+use rand::seq::index;
+
+use crate::{FitnessOrder, Genotype, Individual};
+
+/// strategy for choosing parents from a population
+pub trait Selection {
+    /// choose `count` parents, given the fitness of every member of the population,
+    /// returned as indices into `fitness`
+    fn select_by_fitness<R: Rng + ?Sized>(&self, fitness: &[f64], order: &FitnessOrder, count: usize, rng: &mut R) -> Vec<usize>;
+
+    /// choose `count` parents, given the population and the fitness to select by, one per member,
+    /// which may have been changed from the members' own by an adjustment, see `niching::Adjusted`
+    /// only strategies that look at the genomes as well need to implement this
+    fn select_with_fitness<T: Genotype, R: Rng + ?Sized>(&self, _population: &[Individual<T>], fitness: &[f64], order: &FitnessOrder, count: usize, rng: &mut R) -> Vec<usize> {
+        self.select_by_fitness(fitness, order, count, rng)
+    }
+
+    /// choose `count` parents, returned as indices into `population`
+    fn select<T: Genotype, R: Rng + ?Sized>(&self, population: &[Individual<T>], order: &FitnessOrder, count: usize, rng: &mut R) -> Vec<usize> {
+        let fitness: Vec<f64> = population.iter().map(|individual| individual.fitness()).collect();
+        self.select_with_fitness(population, &fitness, order, count, rng)
+    }
+}
+
+/// # Tournament Selection
+/// pick `size` contestants at random, the fittest of which wins
+/// without replacement, no individual can appear twice in the same tournament
+#[derive(Debug, Clone)]
+pub struct Tournament {
+    pub size: usize,
+    pub with_replacement: bool
+}
+
+impl Tournament {
+    pub fn new(size: usize) -> Self {
+        Tournament { size, with_replacement: true }
+    }
+
+    pub fn without_replacement(size: usize) -> Self {
+        Tournament { size, with_replacement: false }
+    }
+
+    /// hold `count` tournaments among `len` members, each won by the first contestant drawn that `compare` puts first
+    /// shared with the strategies that break ties by more than fitness, see `gp::LexicographicParsimony`
+    pub(crate) fn hold<R, F>(&self, len: usize, count: usize, rng: &mut R, compare: F) -> Vec<usize>
+    where
+        R: Rng + ?Sized,
+        F: Fn(&usize, &usize) -> Ordering
+    {
+        let size = self.size.max(1);
+        if len == 0 {
+            return Vec::new();
+        }
+
+        (0..count).map(|_| {
+            let contestants: Vec<usize> = if self.with_replacement {
+                (0..size).map(|_| rng.gen_range(0..len)).collect()
+            } else {
+                index::sample(rng, len, size.min(len)).into_vec()
+            };
+
+            // first fittest contestant wins, ties go to whoever was drawn first
+            contestants.into_iter()
+                .min_by(&compare)
+                .unwrap()
+        }).collect()
+    }
+}
+
+impl Selection for Tournament {
+    fn select_by_fitness<R: Rng + ?Sized>(&self, fitness: &[f64], order: &FitnessOrder, count: usize, rng: &mut R) -> Vec<usize> {
+        self.hold(fitness.len(), count, rng, |a, b| order.compare(fitness[*a], fitness[*b]))
+    }
+}
+
+/// # Fitness-Proportionate Selection
+/// the classic roulette wheel, spun once per parent
+#[derive(Debug, Clone, Default)]
+pub struct Roulette;
+
+impl Selection for Roulette {
+    fn select_by_fitness<R: Rng + ?Sized>(&self, fitness: &[f64], order: &FitnessOrder, count: usize, rng: &mut R) -> Vec<usize> {
+        sample_weighted(&proportional_weights(fitness, order), count, rng)
+    }
+}
+
+/// # Stochastic Universal Sampling
+/// one spin of a wheel with `count` evenly spaced pointers
+/// same expected counts as roulette, but with minimal spread
+#[derive(Debug, Clone, Default)]
+pub struct StochasticUniversal;
+
+impl Selection for StochasticUniversal {
+    fn select_by_fitness<R: Rng + ?Sized>(&self, fitness: &[f64], order: &FitnessOrder, count: usize, rng: &mut R) -> Vec<usize> {
+        let weights = proportional_weights(fitness, order);
+        let total: f64 = weights.iter().sum();
+
+        if count == 0 || fitness.is_empty() {
+            return Vec::new();
+        }
+        if total <= 0.0 || !total.is_finite() {
+            return (0..count).map(|_| rng.gen_range(0..fitness.len())).collect();
+        }
+
+        let step = total / count as f64;
+        let mut pointer = rng.gen::<f64>() * step;
+        let mut cumulative = weights[0];
+        let mut idx = 0;
+        let mut selected = Vec::with_capacity(count);
+
+        for _ in 0..count {
+            while pointer > cumulative && idx < weights.len() - 1 {
+                idx += 1;
+                cumulative += weights[idx];
+            }
+            selected.push(idx);
+            pointer += step;
+        }
+
+        selected
+    }
+}
+
+/// # Linear Rank Selection
+/// probability depends only on rank, not on raw fitness
+/// `pressure` is the expected number of copies of the best individual, in [1, 2]
+#[derive(Debug, Clone)]
+pub struct LinearRank {
+    pub pressure: f64
+}
+
+impl Selection for LinearRank {
+    fn select_by_fitness<R: Rng + ?Sized>(&self, fitness: &[f64], order: &FitnessOrder, count: usize, rng: &mut R) -> Vec<usize> {
+        let ranked = ranked(fitness, order);
+        let n = ranked.len() as f64;
+        let sp = self.pressure.clamp(1.0, 2.0);
+
+        // rank 0 is fittest, and gets weight sp, down to 2 - sp for the least fit
+        let mut weights = vec![0.0; fitness.len()];
+        for (rank, idx) in ranked.iter().enumerate() {
+            weights[*idx] = if n > 1.0 {
+                sp - (2.0 * (sp - 1.0) * rank as f64) / (n - 1.0)
+            } else {
+                1.0
+            };
+        }
+
+        sample_weighted(&weights, count, rng)
+    }
+}
+
+/// # Exponential Rank Selection
+/// weight of the individual at rank i (0 being fittest) is `base^i`, with `base` in (0, 1)
+/// smaller bases give a stronger selection pressure
+#[derive(Debug, Clone)]
+pub struct ExponentialRank {
+    base: f64
+}
+
+impl ExponentialRank {
+    pub fn new(base: f64) -> Result<Self, Box<dyn Error>> {
+        if !(base > 0.0 && base < 1.0) {
+            return Err(format!("exponential rank base must be in (0, 1), not {}", base).into());
+        }
+        Ok(ExponentialRank { base })
+    }
+
+    pub fn get_base(&self) -> f64 {
+        self.base
+    }
+}
+
+impl Selection for ExponentialRank {
+    fn select_by_fitness<R: Rng + ?Sized>(&self, fitness: &[f64], order: &FitnessOrder, count: usize, rng: &mut R) -> Vec<usize> {
+        let ranked = ranked(fitness, order);
+
+        let mut weights = vec![0.0; fitness.len()];
+        for (rank, idx) in ranked.iter().enumerate() {
+            weights[*idx] = self.base.powi(rank as i32);
+        }
+
+        sample_weighted(&weights, count, rng)
+    }
+}
+
+/// # Truncation Selection
+/// only the fittest `proportion` of the population can be chosen, uniformly at random
+#[derive(Debug, Clone)]
+pub struct Truncation {
+    pub proportion: f64
+}
+
+impl Selection for Truncation {
+    fn select_by_fitness<R: Rng + ?Sized>(&self, fitness: &[f64], order: &FitnessOrder, count: usize, rng: &mut R) -> Vec<usize> {
+        let ranked = ranked(fitness, order);
+        if ranked.is_empty() {
+            return Vec::new();
+        }
+        let cutoff = ((self.proportion * ranked.len() as f64).ceil() as usize).clamp(1, ranked.len());
+
+        (0..count).map(|_| ranked[rng.gen_range(0..cutoff)]).collect()
+    }
+}
+
+/// # Boltzmann Selection
+/// weight is `exp(f / temperature)` (or `exp(-f / temperature)` when minimising)
+/// high temperatures are close to uniform, low temperatures close to picking the best
+#[derive(Debug, Clone)]
+pub struct Boltzmann {
+    temperature: f64
+}
+
+impl Boltzmann {
+    /// at a temperature of 0 or below, the weights are all 0 or nan
+    pub fn new(temperature: f64) -> Result<Self, Box<dyn Error>> {
+        if temperature.is_nan() || temperature <= 0.0 {
+            return Err(format!("boltzmann temperature must be positive, not {}", temperature).into());
+        }
+        Ok(Boltzmann { temperature })
+    }
+
+    pub fn get_temperature(&self) -> f64 {
+        self.temperature
+    }
+}
+
+impl Selection for Boltzmann {
+    fn select_by_fitness<R: Rng + ?Sized>(&self, fitness: &[f64], order: &FitnessOrder, count: usize, rng: &mut R) -> Vec<usize> {
+        let best = fitness.iter()
+            .copied()
+            .min_by(|a, b| order.compare(*a, *b))
+            .unwrap_or(0.0);
+
+        // shifted by the best fitness so the exponent is never positive and can't overflow
+        let weights: Vec<f64> = fitness.iter()
+            .map(|f| {
+                let delta = (f - best).abs();
+                (-delta / self.temperature).exp()
+            })
+            .collect();
+
+        sample_weighted(&weights, count, rng)
+    }
+}
+
+/// indices of the population sorted with the fittest first
+fn ranked(fitness: &[f64], order: &FitnessOrder) -> Vec<usize> {
+    let mut indices: Vec<usize> = (0..fitness.len()).collect();
+    indices.sort_by(|a, b| order.compare(fitness[*a], fitness[*b]));
+    indices
+}
+
+/// non-negative weights proportional to fitness
+/// shifted by the least fit so that minimisation and negative fitness both work
+fn proportional_weights(fitness: &[f64], order: &FitnessOrder) -> Vec<f64> {
+    let worst = fitness.iter()
+        .copied()
+        .max_by(|a, b| order.compare(*a, *b))
+        .unwrap_or(0.0);
+
+    fitness.iter()
+        .map(|f| (f - worst).abs())
+        .collect()
+}
+
+/// spin a roulette wheel `count` times
+/// falls back to uniform if all weights are zero, e.g. a fully converged population
+fn sample_weighted<R: Rng + ?Sized>(weights: &[f64], count: usize, rng: &mut R) -> Vec<usize> {
+    if weights.is_empty() {
+        return Vec::new();
+    }
+
+    match WeightedIndex::new(weights) {
+        Ok(distribution) => (0..count).map(|_| distribution.sample(rng)).collect(),
+        Err(_) => (0..count).map(|_| rng.gen_range(0..weights.len())).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    #[test]
+    fn constructors_reject_bad_parameters() {
+        assert!(Boltzmann::new(0.0).is_err());
+        assert!(Boltzmann::new(-1.0).is_err());
+        assert!(Boltzmann::new(f64::NAN).is_err());
+        assert_eq!(Boltzmann::new(0.5).unwrap().get_temperature(), 0.5);
+
+        for base in [0.0, 1.0, -0.5, 1.5, f64::NAN] {
+            assert!(ExponentialRank::new(base).is_err(), "base {}", base);
+        }
+        assert_eq!(ExponentialRank::new(0.9).unwrap().get_base(), 0.9);
+    }
+
+    #[test]
+    fn empty_population_gives_no_parents() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let order = FitnessOrder::Max;
+
+        assert!(Tournament::new(3).select_by_fitness(&[], &order, 4, &mut rng).is_empty());
+        assert!(Tournament::without_replacement(3).select_by_fitness(&[], &order, 4, &mut rng).is_empty());
+        assert!(Roulette.select_by_fitness(&[], &order, 4, &mut rng).is_empty());
+        assert!(StochasticUniversal.select_by_fitness(&[], &order, 4, &mut rng).is_empty());
+        assert!(LinearRank { pressure: 1.5 }.select_by_fitness(&[], &order, 4, &mut rng).is_empty());
+        assert!(ExponentialRank::new(0.9).unwrap().select_by_fitness(&[], &order, 4, &mut rng).is_empty());
+        assert!(Truncation { proportion: 0.5 }.select_by_fitness(&[], &order, 4, &mut rng).is_empty());
+        assert!(Boltzmann::new(1.0).unwrap().select_by_fitness(&[], &order, 4, &mut rng).is_empty());
+    }
+}
@@ -0,0 +1,182 @@
+/// # Population Statistics
+///
+/// a snapshot of the population taken after every epoch,
+/// used to diagnose premature convergence
+///
+/// the fitness statistics are cheap, but the diversity statistics compare every pair of genomes,
+/// so they're only worked out when something asks for them, see `Generation::set_diversity_tracking`
+use rayon::prelude::*;
+
+use crate::{FitnessOrder, Genotype, Individual};
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Statistics {
+    /// fittest value, respecting the `FitnessOrder`
+    pub best: f64,
+    /// least fit value, respecting the `FitnessOrder`
+    pub worst: f64,
+    pub mean: f64,
+    pub median: f64,
+    pub std_dev: f64,
+    /// numerically lower quartile, regardless of `FitnessOrder`
+    pub lower_quartile: f64,
+    /// numerically upper quartile, regardless of `FitnessOrder`
+    pub upper_quartile: f64,
+    /// number of distinct genomes, i.e. no other genome is at distance 0
+    /// `None` unless diversity is being tracked
+    pub unique: Option<usize>,
+    /// mean pairwise genome distance, 0 meaning every member is identical
+    /// `None` unless diversity is being tracked
+    pub diversity: Option<f64>
+}
+
+impl Statistics {
+    /// names of the columns written by `to_record`, in the same order
+    pub const HEADERS: [&'static str; 9] = ["best_fitness", "average_fitness", "worst_fitness", "median_fitness", "std_dev", "lower_quartile", "upper_quartile", "unique", "diversity"];
+
+    /// with `diversity` this is quadratic in the population size, as every pair of genomes is compared
+    pub fn new<T: Genotype + Sync>(population: &[Individual<T>], order: &FitnessOrder, diversity: bool) -> Self {
+        if population.is_empty() {
+            return Statistics::default();
+        }
+
+        let mut fitness: Vec<f64> = population.iter()
+            .map(|individual| individual.fitness())
+            .collect();
+        let len = fitness.len() as f64;
+
+        let mean = fitness.iter().sum::<f64>() / len;
+        let variance = fitness.iter()
+            .map(|f| (f - mean).powi(2))
+            .sum::<f64>() / len;
+
+        fitness.sort_by(|a, b| a.total_cmp(b));
+        let (lowest, highest) = (fitness[0], fitness[fitness.len() - 1]);
+        let (best, worst) = if *order == FitnessOrder::Max {
+            (highest, lowest)
+        } else {
+            (lowest, highest)
+        };
+
+        let (diversity, unique) = if diversity {
+            let (diversity, unique) = pairwise(population);
+            (Some(diversity), Some(unique))
+        } else {
+            (None, None)
+        };
+
+        Statistics {
+            best,
+            worst,
+            mean,
+            median: quantile(&fitness, 0.5),
+            std_dev: variance.sqrt(),
+            lower_quartile: quantile(&fitness, 0.25),
+            upper_quartile: quantile(&fitness, 0.75),
+            unique,
+            diversity
+        }
+    }
+
+    /// values as csv fields, see `HEADERS`
+    /// diversity statistics that weren't worked out are left empty
+    pub fn to_record(&self) -> Vec<String> {
+        vec![
+            self.best.to_string(),
+            self.mean.to_string(),
+            self.worst.to_string(),
+            self.median.to_string(),
+            self.std_dev.to_string(),
+            self.lower_quartile.to_string(),
+            self.upper_quartile.to_string(),
+            self.unique.map(|unique| unique.to_string()).unwrap_or_default(),
+            self.diversity.map(|diversity| diversity.to_string()).unwrap_or_default()
+        ]
+    }
+}
+
+/// quantile of already sorted values, linearly interpolated between the closest ranks
+fn quantile(sorted: &[f64], q: f64) -> f64 {
+    let position = q * (sorted.len() - 1) as f64;
+    let lower = position.floor() as usize;
+    let upper = position.ceil() as usize;
+    sorted[lower] + (sorted[upper] - sorted[lower]) * (position - lower as f64)
+}
+
+/// mean pairwise distance, and the number of genomes with no identical genome before them
+fn pairwise<T: Genotype + Sync>(population: &[Individual<T>]) -> (f64, usize) {
+    let len = population.len();
+    if len < 2 {
+        return (0.0, len);
+    }
+
+    // each row compares genome i against every genome after it,
+    // noting any later genome that is identical to it
+    // collected before summing, a parallel sum isn't reproducible
+    let rows: Vec<(f64, Vec<usize>)> = (0..len).into_par_iter()
+        .map(|i| {
+            let mut total = 0.0;
+            let mut identical = Vec::new();
+            for j in i + 1..len {
+                let distance = population[i].genome().distance(population[j].genome());
+                if distance == 0.0 {
+                    identical.push(j);
+                }
+                total += distance;
+            }
+            (total, identical)
+        })
+        .collect();
+
+    let mut duplicate = vec![false; len];
+    for j in rows.iter().flat_map(|(_, identical)| identical) {
+        duplicate[*j] = true;
+    }
+
+    let total: f64 = rows.iter().map(|(total, _)| total).sum();
+    let unique = duplicate.iter().filter(|d| !**d).count();
+
+    (total / (len * (len - 1) / 2) as f64, unique)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bitstring::{Bitstring, BitstringProblem};
+    use crate::test_util::OneMax;
+
+    /// fitness 0, 1, 1, 3 and 4, with the two 1s the same genome
+    fn population() -> Vec<Individual<Bitstring>> {
+        let problem: BitstringProblem<OneMax, Bitstring> = BitstringProblem::new(4, OneMax);
+        [[0, 0, 0, 0], [1, 0, 0, 0], [1, 1, 1, 0], [1, 0, 0, 0], [1, 1, 1, 1]].into_iter()
+            .map(|bits| Individual::new(&problem, Bitstring::new(bits.to_vec())))
+            .collect()
+    }
+
+    #[test]
+    fn fixed_population() {
+        for (order, best, worst) in [(FitnessOrder::Max, 4.0, 0.0), (FitnessOrder::Min, 0.0, 4.0)] {
+            let statistics = Statistics::new(&population(), &order, true);
+
+            assert_eq!((statistics.best, statistics.worst), (best, worst));
+            assert!((statistics.mean - 1.8).abs() < 1e-12);
+            assert!((statistics.std_dev - 2.16f64.sqrt()).abs() < 1e-12);
+            // the quartiles are numerical, whichever way is fitter
+            assert_eq!((statistics.lower_quartile, statistics.median, statistics.upper_quartile), (1.0, 1.0, 3.0));
+            assert_eq!(statistics.unique, Some(4));
+            // hamming distances over the 10 pairs add up to 20
+            assert_eq!(statistics.diversity, Some(2.0));
+        }
+
+        let untracked = Statistics::new(&population(), &FitnessOrder::Max, false);
+        assert_eq!((untracked.unique, untracked.diversity), (None, None));
+    }
+
+    #[test]
+    fn quantiles_interpolate() {
+        let sorted = [1.0, 2.0, 3.0, 4.0];
+        assert_eq!(quantile(&sorted, 0.25), 1.75);
+        assert_eq!(quantile(&sorted, 0.5), 2.5);
+        assert_eq!(quantile(&[7.0], 0.75), 7.0);
+    }
+}
@@ -0,0 +1,166 @@
+/// # Steady-State GA
+///
+/// instead of replacing the whole population at once, one or two children
+/// are bred at a time and each one replaces a single member of the population
+///
+/// each call to `steady_state_epoch` runs for a fixed number of fitness evaluations,
+/// so with `report_every` set to the population size one call is comparable to one
+/// generational `epoch`, and the same per-epoch csv rows can be written after it
+use rand::Rng;
+
+use crate::{FitnessOrder, Generation, Genotype, Individual, Problem};
+use crate::selection::Selection;
+
+/// which member of the population makes room for each new child
+#[derive(Debug, Clone, PartialEq)]
+pub enum SteadyStateReplacement {
+    /// the least fit member
+    Worst,
+    /// any member, uniformly at random
+    Random,
+    /// the member that has been in the population longest
+    Oldest,
+    /// the loser of a tournament of the given size
+    TournamentLoser { size: usize }
+}
+
+#[derive(Debug, Clone)]
+pub struct SteadyState {
+    /// children inserted per step, either 1 or 2
+    pub offspring: usize,
+    pub replacement: SteadyStateReplacement,
+    /// fitness evaluations per call to `steady_state_epoch`
+    pub report_every: usize
+}
+
+impl SteadyState {
+    pub fn new(offspring: usize, replacement: SteadyStateReplacement, report_every: usize) -> Self {
+        SteadyState {
+            offspring: offspring.clamp(1, 2),
+            replacement,
+            report_every
+        }
+    }
+}
+
+/// run the steady-state loop for exactly `scheme.report_every` fitness evaluations
+/// does nothing if that's 0 or the population is empty, as there'd be nothing to replace
+pub fn steady_state_epoch<P, S, R>(gen: &mut Generation<P::Genome>, problem: &P, order: &FitnessOrder, selection: &S, scheme: &SteadyState, rng: &mut R)
+where
+    P: Problem,
+    S: Selection,
+    R: Rng + ?Sized
+{
+    if gen.population.is_empty() || scheme.report_every == 0 {
+        return;
+    }
+
+    let target = gen.evaluations + scheme.report_every;
+    gen.apply_constraints(&mut [], order);
+
+    while gen.evaluations < target {
+        let parents = selection.select(&gen.population, order, 2, rng);
+
+        let (mut offspring, origins) = gen.breed(problem, &parents, rng);
+        // the last step may only have room for one child
+        offspring.truncate(scheme.offspring.min(target - gen.evaluations));
+        let mut offspring = gen.evaluate(problem, offspring);
+        gen.apply_constraints(&mut offspring, order);
+        gen.feedback(problem, &parents, &offspring, &origins, order);
+
+        for child in offspring {
+            let victim = choose_victim(&gen.population, order, &scheme.replacement, rng);
+            gen.population[victim] = child;
+        }
+    }
+
+    gen.finish_epoch(problem, order, rng);
+}
+
+/// index of the member to be replaced, the population must not be empty
+fn choose_victim<T: Genotype, R: Rng + ?Sized>(population: &[Individual<T>], order: &FitnessOrder, replacement: &SteadyStateReplacement, rng: &mut R) -> usize {
+    let least_fit = |a: &usize, b: &usize| order.compare(population[*a].fitness(), population[*b].fitness());
+
+    match replacement {
+        SteadyStateReplacement::Worst => {
+            (0..population.len()).max_by(least_fit).unwrap()
+        },
+        SteadyStateReplacement::Random => {
+            rng.gen_range(0..population.len())
+        },
+        SteadyStateReplacement::Oldest => {
+            (0..population.len()).min_by_key(|idx| population[*idx].born()).unwrap()
+        },
+        SteadyStateReplacement::TournamentLoser { size } => {
+            (0..(*size).max(1))
+                .map(|_| rng.gen_range(0..population.len()))
+                .max_by(least_fit)
+                .unwrap()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+    use crate::bitstring::{Bitstring, BitstringProblem};
+    use crate::selection::Tournament;
+    use crate::test_util::OneMax;
+
+    /// fitness 1, 4, 2, 3, with the last member the oldest
+    fn population() -> Generation<Bitstring> {
+        let problem: BitstringProblem<OneMax, Bitstring> = BitstringProblem::new(4, OneMax);
+        let mut generation = Generation::new(4);
+        for ones in [3, 1, 4, 2] {
+            generation.push(&problem, Bitstring::new((0..4).map(|idx| (idx < ones) as u8).collect()));
+        }
+        generation.population.rotate_left(1);
+        generation
+    }
+
+    #[test]
+    fn victims() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let population = population().population;
+
+        assert_eq!(choose_victim(&population, &FitnessOrder::Max, &SteadyStateReplacement::Worst, &mut rng), 0);
+        assert_eq!(choose_victim(&population, &FitnessOrder::Min, &SteadyStateReplacement::Worst, &mut rng), 1);
+        assert_eq!(choose_victim(&population, &FitnessOrder::Max, &SteadyStateReplacement::Oldest, &mut rng), 3);
+
+        // a tournament this big is all but certain to include every member
+        let loser = SteadyStateReplacement::TournamentLoser { size: 100 };
+        assert_eq!(choose_victim(&population, &FitnessOrder::Max, &loser, &mut rng), 0);
+        assert_eq!(choose_victim(&population, &FitnessOrder::Min, &loser, &mut rng), 1);
+    }
+
+    #[test]
+    fn spends_exactly_report_every_evaluations() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let problem: BitstringProblem<OneMax, Bitstring> = BitstringProblem::new(4, OneMax);
+        let mut generation = population();
+
+        // an odd budget with two children per step
+        let scheme = SteadyState::new(2, SteadyStateReplacement::Worst, 5);
+        steady_state_epoch(&mut generation, &problem, &FitnessOrder::Max, &Tournament::new(2), &scheme, &mut rng);
+        assert_eq!(generation.get_evaluations(), 4 + 5);
+        assert_eq!(generation.population.len(), 4);
+    }
+
+    #[test]
+    fn nothing_to_do() {
+        let mut rng = StdRng::seed_from_u64(2);
+        let problem: BitstringProblem<OneMax, Bitstring> = BitstringProblem::new(4, OneMax);
+
+        let mut empty = Generation::new(4);
+        let scheme = SteadyState::new(1, SteadyStateReplacement::Random, 10);
+        steady_state_epoch(&mut empty, &problem, &FitnessOrder::Max, &Tournament::new(2), &scheme, &mut rng);
+        assert_eq!(empty.get_evaluations(), 0);
+
+        let mut generation = population();
+        let scheme = SteadyState::new(1, SteadyStateReplacement::Random, 0);
+        steady_state_epoch(&mut generation, &problem, &FitnessOrder::Max, &Tournament::new(2), &scheme, &mut rng);
+        assert_eq!(generation.get_evaluations(), 4);
+    }
+}
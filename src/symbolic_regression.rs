@@ -0,0 +1,42 @@
+use genetic_algorithms::gp::{PrimitiveSet, Tree, TreeFitness};
+
+/// # Symbolic Regression
+/// find an expression in x matching a target function at evenly spaced points,
+/// by default koza's quartic `x^4 + x^3 + x^2 + x` at 20 points in [-1, 1]
+#[derive(Clone, Debug)]
+pub struct SymbolicRegression {
+    cases: Vec<(f64, f64)>
+}
+
+impl SymbolicRegression {
+    pub fn quartic() -> Self {
+        let cases = (0..20)
+            .map(|i| -1.0 + 2.0 * i as f64 / 19.0)
+            .map(|x| (x, x.powi(4) + x.powi(3) + x.powi(2) + x))
+            .collect();
+        SymbolicRegression { cases }
+    }
+
+    /// koza's arithmetic functions, with division by 0 giving 1 so every tree evaluates,
+    /// and the single input x
+    pub fn primitives() -> PrimitiveSet<f64> {
+        PrimitiveSet::new()
+            .with_function("add", 2, |args| args[0] + args[1])
+            .with_function("sub", 2, |args| args[0] - args[1])
+            .with_function("mul", 2, |args| args[0] * args[1])
+            .with_function("div", 2, |args| if args[1] == 0.0 { 1.0 } else { args[0] / args[1] })
+            .with_input("x", 0)
+    }
+}
+
+impl TreeFitness<f64> for SymbolicRegression {
+    /// total absolute error over the cases, to be minimised
+    fn fitness(&self, tree: &Tree, primitives: &PrimitiveSet<f64>) -> f64 {
+        let error: f64 = self.cases.iter()
+            .map(|(x, y)| (tree.evaluate(primitives, &[*x]) - y).abs())
+            .sum();
+
+        // a tree blowing up to infinity or nan is just very bad, so the average fitness stays finite
+        if error.is_finite() { error.min(1e12) } else { 1e12 }
+    }
+}
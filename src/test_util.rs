@@ -0,0 +1,12 @@
+/// fixtures shared by the tests of several modules
+use crate::bitstring::{Bits, BitstringFitness};
+
+/// count the ones, the simplest fitness there is
+#[derive(Debug, Clone)]
+pub struct OneMax;
+
+impl BitstringFitness for OneMax {
+    fn fitness<B: Bits>(&self, genome: &B) -> f64 {
+        genome.ones() as f64
+    }
+}
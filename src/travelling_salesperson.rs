@@ -3,99 +3,255 @@
 /// crossover operators:
 /// - PMX
 /// - OX
+///
 /// see comparative analysis of c.o. by KUMAR, KUMAR, KARAMBIR
 ///
 /// mutation operators:
 /// - RSM
 /// - PSM
 /// - SM
+///
 /// see mutation operators by ABDOUN, ABOUCHABAKA, TAJANI
-
-use std::{collections::HashSet, error::Error, time::Instant};
+///
+/// rates are either fixed for the whole run, or self-adaptive,
+/// see self-adaptation in evolutionary algorithms by MEYER-NIEBERG, BEYER
+///
+/// which operator is used is either fixed by weight, or adapted to how well each is doing, see `operators`
+/// the operators themselves work on any permutation, see `permutation`
+use std::{error::Error, time::Instant};
 use std::sync::Arc;
-use bimap::BiMap;
-use rand::{thread_rng, Rng};
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
 use rand::seq::SliceRandom;
 use std::process::Command;
-use std::fs::File;
+use std::fs::{self, File};
 use std::io::BufWriter;
-use csv::Writer;
+use std::path::PathBuf;
+use csv::{ReaderBuilder, Writer};
 use tspf::{self, Tsp, TspBuilder};
-use genetic_algorithms::{epoch, FitnessOrder, Generation, Genotype};
-
+use genetic_algorithms::{gaussian, initialise, Engine, FitnessOrder, Generation, Genotype, Individual, Model, Origin, Problem, RunSummary, Termination, Variation};
+use genetic_algorithms::checkpoint::{Checkpoint, CheckpointRng, Checkpointing};
+use genetic_algorithms::diversity::{Reseed, Restart};
+use genetic_algorithms::hall_of_fame::HallOfFame;
+use genetic_algorithms::island::{IslandModel, Migration};
+use genetic_algorithms::nsga2::{MultiObjective, Nsga2};
+use genetic_algorithms::observer::{CsvObserver, Observer};
+use genetic_algorithms::operators::{Adaptation, OperatorPool};
+use genetic_algorithms::permutation::{Crossover, Mutation, Permutation};
+use genetic_algorithms::selection::Tournament;
+use genetic_algorithms::replacement::Replacement;
+
+/// a tour, and its own rates if they are self-adaptive
+/// everything shared by every tour is in `TravellingSalesperson`
 #[derive(Debug, Clone)]
 pub struct TSPath {
-    data: Arc<Tsp>,
-    path: Vec<usize>,
-    mutation_rate: f64,
-    crossover_rate: f64
+    path: Permutation,
+    rates: Option<Rates>
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rates {
+    pub mutation: f64,
+    pub crossover: f64
 }
 
+/// self-adaptive rates are kept within these bounds, so that neither operator can die out completely
+const MUTATION_RATE_BOUNDS: (f64, f64) = (0.001, 1.0);
+const CROSSOVER_RATE_BOUNDS: (f64, f64) = (0.1, 1.0);
+
+/// self-adaptive tours start with rates drawn uniformly from the same ranges as the grid search
+const INITIAL_MUTATION_RATES: (f64, f64) = (0.05, 0.1);
+const INITIAL_CROSSOVER_RATES: (f64, f64) = (0.8, 1.0);
+
+/// weighted in order of increasing destructiveness, to begin with at least
+const MUTATIONS: [Mutation; 3] = [Mutation::Swap, Mutation::ReverseSequence, Mutation::PartialShuffle];
+const MUTATION_WEIGHTS: [f64; 3] = [0.25, 0.5, 0.25];
+const CROSSOVERS: [Crossover; 2] = [Crossover::PartiallyMapped, Crossover::Order];
+const CROSSOVER_WEIGHTS: [f64; 2] = [0.5, 0.5];
+
 impl TSPath {
+    pub fn length(&self) -> usize {
+        self.path.len()
+    }
+
+    pub fn get_path(&self) -> &[usize] {
+        self.path.order()
+    }
+
+    /// the tour's own rates, `None` unless they are self-adaptive
+    pub fn rates(&self) -> Option<Rates> {
+        self.rates
+    }
+}
+
+/// # Travelling Salesperson
+/// the dataset and operator rates, shared by every tour
+#[derive(Debug, Clone)]
+pub struct TravellingSalesperson {
+    data: Arc<Tsp>,
+    /// every city in the dataset, in order, shuffled to make a random tour
+    cities: Vec<usize>,
+    /// used by every tour that doesn't have rates of its own
+    rates: Rates,
+    /// standard deviation of the log-normal perturbation of the rates, if they are self-adaptive
+    learning_rate: Option<f64>,
+    crossovers: OperatorPool,
+    mutations: OperatorPool
+}
+
+impl TravellingSalesperson {
     pub fn new(dataset: Arc<Tsp>, mutation_rate: f64, crossover_rate: f64) -> Self {
-        let nodes = dataset.node_coords();
-        let mut keys: Vec<usize> = nodes.keys().cloned().collect();
-
-        // perform fisher-yates shuffle
-        // hash key vector is randomised per run of the program
-        // but not per instance of the ga
-        keys.shuffle(&mut thread_rng());
-
-        TSPath {
-            data : dataset.clone(),
-            path : keys,
-            mutation_rate,
-            crossover_rate
+        let mut cities: Vec<usize> = dataset.node_coords().keys().cloned().collect();
+
+        // hash key order is randomised per run of the program,
+        // so sort first or the same seed won't give the same tour
+        cities.sort_unstable();
+
+        TravellingSalesperson {
+            data: dataset,
+            cities,
+            rates: Rates { mutation: mutation_rate, crossover: crossover_rate },
+            learning_rate: None,
+            crossovers: OperatorPool::new(&CROSSOVERS.map(|crossover| crossover.name()), &CROSSOVER_WEIGHTS, Adaptation::Fixed),
+            mutations: OperatorPool::new(&MUTATIONS.map(|mutation| mutation.name()), &MUTATION_WEIGHTS, Adaptation::Fixed)
         }
     }
 
-    pub fn length(&self) -> usize {
-        self.path.len()
+    /// adapt which crossover and mutation operators are used, starting from their fixed weights
+    pub fn with_operator_adaptation(self, adaptation: Adaptation) -> Self {
+        TravellingSalesperson {
+            crossovers: self.crossovers.with_adaptation(adaptation.clone()),
+            mutations: self.mutations.with_adaptation(adaptation),
+            ..self
+        }
     }
 
-    pub fn get_path(&self) -> &Vec<usize> {
-        &self.path
+    pub fn crossovers(&self) -> &OperatorPool {
+        &self.crossovers
     }
-}
 
-impl Genotype for TSPath {
-    fn crossover(x: &Self, y: &Self) -> (Self, Self) {
-        let mut rng = thread_rng();
+    pub fn mutations(&self) -> &OperatorPool {
+        &self.mutations
+    }
 
-        // check that crossover will occur
-        if rng.gen::<f64>() < x.crossover_rate {
-            // choose which crossover operation occurs
-            if rng.gen_bool(0.5) {
-                return partially_mapped_crossover(x, y)
-            } else {
-                return order_crossover(x, y)
-            }
+    /// # Self-Adaptive Rates
+    /// the mutation and crossover rates become part of each tour
+    /// children inherit the mean of their parents' rates,
+    /// and perturb them log-normally before mutating, so rates that produce good children survive with them
+    ///
+    /// random tours draw their starting rates from the ranges of the grid search,
+    /// the middle of which is used for any tour without rates of its own
+    pub fn self_adaptive(dataset: Arc<Tsp>, learning_rate: f64) -> Self {
+        TravellingSalesperson {
+            learning_rate: Some(learning_rate),
+            ..TravellingSalesperson::new(
+                dataset,
+                (INITIAL_MUTATION_RATES.0 + INITIAL_MUTATION_RATES.1) / 2.0,
+                (INITIAL_CROSSOVER_RATES.0 + INITIAL_CROSSOVER_RATES.1) / 2.0
+            )
         }
+    }
 
-        (x.clone(), y.clone())
+    /// the rates a tour is bred with, its own if it has them
+    pub fn rates(&self, path: &TSPath) -> Rates {
+        path.rates.unwrap_or(self.rates)
     }
 
-    fn mutation(&self) -> Self {
-        let mut rng = thread_rng();
+    /// apply one of the mutation operators, with probability given by the mutation rate
+    fn mutate_path<R: Rng + ?Sized>(&self, path: &TSPath, rng: &mut R) -> (TSPath, Option<usize>) {
+        let mut child = path.clone();
 
         // check that mutation will occur
-        if rng.gen::<f64>() < self.mutation_rate {
+        if rng.gen::<f64>() < self.rates(path).mutation {
             // choose which mutation operation occurs
+            let operator = self.mutations.choose(rng);
+            MUTATIONS[operator].apply(&mut child.path, rng);
+            return (child, Some(operator))
+        }
+
+        (child, None)
+    }
+}
+
+/// intermediate recombination of the parents' rates
+fn inherit_rates(x: Rates, y: Rates) -> Rates {
+    Rates {
+        mutation: (x.mutation + y.mutation) / 2.0,
+        crossover: (x.crossover + y.crossover) / 2.0
+    }
+}
+
+/// multiply a rate by a log-normally distributed factor, keeping it within bounds
+fn perturb<R: Rng + ?Sized>(rate: f64, learning_rate: f64, bounds: (f64, f64), rng: &mut R) -> f64 {
+    (rate * (learning_rate * gaussian(rng)).exp()).clamp(bounds.0, bounds.1)
+}
+
+impl Variation for TravellingSalesperson {
+    type Genome = TSPath;
 
-            // probabilities weighted in order of increasing destructiveness
-            let operator = rng.gen_range(1..=100);
-            if operator <= 25 {
-                return swap_mutation(self)
-            } else if operator <= 75 {
-                return reverse_sequence_mutation(self)
-            } else {
-                return partial_shuffle_mutation(self)
+    /// a uniformly random tour, using a fisher-yates shuffle
+    fn random<R: Rng + ?Sized>(&self, rng: &mut R) -> TSPath {
+        let rates = self.learning_rate.map(|_| Rates {
+            mutation: rng.gen_range(INITIAL_MUTATION_RATES.0..=INITIAL_MUTATION_RATES.1),
+            crossover: rng.gen_range(INITIAL_CROSSOVER_RATES.0..=INITIAL_CROSSOVER_RATES.1)
+        });
+
+        let mut path = self.cities.clone();
+        path.shuffle(rng);
+
+        TSPath { path: Permutation::new(path), rates }
+    }
+
+    fn crossover<R: Rng + ?Sized>(&self, x: &TSPath, y: &TSPath, rng: &mut R) -> (TSPath, TSPath) {
+        let (child_0, child_1, _) = self.crossover_with_operator(x, y, rng);
+        (child_0, child_1)
+    }
+
+    fn mutation<R: Rng + ?Sized>(&self, path: &TSPath, rng: &mut R) -> TSPath {
+        self.mutation_with_operator(path, rng).0
+    }
+
+    fn crossover_with_operator<R: Rng + ?Sized>(&self, x: &TSPath, y: &TSPath, rng: &mut R) -> (TSPath, TSPath, Option<usize>) {
+        let (x_rates, y_rates) = (self.rates(x), self.rates(y));
+
+        // check that crossover will occur
+        // the parents' rates are only different if they are self-adaptive
+        if rng.gen::<f64>() < (x_rates.crossover + y_rates.crossover) / 2.0 {
+            // choose which crossover operation occurs
+            let operator = self.crossovers.choose(rng);
+            let (path_0, path_1) = CROSSOVERS[operator].apply(&x.path, &y.path, rng);
+
+            let mut child_0 = TSPath { path: path_0, rates: x.rates };
+            let mut child_1 = TSPath { path: path_1, rates: y.rates };
+
+            if self.learning_rate.is_some() {
+                child_0.rates = Some(inherit_rates(x_rates, y_rates));
+                child_1.rates = Some(inherit_rates(x_rates, y_rates));
             }
+
+            return (child_0, child_1, Some(operator))
         }
 
-        self.clone()
+        // copies of the parents, which weren't made by any operator
+        (x.clone(), y.clone(), None)
     }
 
+    fn mutation_with_operator<R: Rng + ?Sized>(&self, path: &TSPath, rng: &mut R) -> (TSPath, Option<usize>) {
+        // perturb the rates first, so they are judged by the child they produce
+        if let (Some(learning_rate), Some(rates)) = (self.learning_rate, path.rates) {
+            let mut adapted = path.clone();
+            adapted.rates = Some(Rates {
+                mutation: perturb(rates.mutation, learning_rate, MUTATION_RATE_BOUNDS, rng),
+                crossover: perturb(rates.crossover, learning_rate, CROSSOVER_RATE_BOUNDS, rng)
+            });
+            return self.mutate_path(&adapted, rng)
+        }
+
+        self.mutate_path(path, rng)
+    }
+}
+
+impl Problem for TravellingSalesperson {
     /// # fitness of solution
     /// represented as the total length of the round trip
     /// all cities are connected, and we use euclidean distances
@@ -104,18 +260,19 @@ impl Genotype for TSPath {
     /// - berlin52: 7542
     /// - kroA100: 21282
     /// - pr1002: 259045
+    ///
     /// see symmetric tsp
     #[allow(clippy::get_first)]
-    fn fitness(&self) -> f64 {
+    fn fitness(&self, path: &TSPath) -> f64 {
         let mut total_distance = 0.0;
-        let len = self.length();
+        let len = path.length();
         let map = self.data.node_coords();
 
         // get euclidiean distance between c and c + 1, wrapping back to start
         // \sqrt{(x_2 - x_1)^2 + (y_2 - y_1)^2}
         for c in 0..len {
-            let c_0 = self.path.get(c).unwrap();
-            let c_1 = self.path.get((c + 1) % len).unwrap();
+            let c_0 = path.get_path().get(c).unwrap();
+            let c_1 = path.get_path().get((c + 1) % len).unwrap();
 
             let pos_c_0 = map.get(c_0).expect("city not found").pos();
             let pos_c_1 = map.get(c_1).expect("city not found").pos();
@@ -128,204 +285,360 @@ impl Genotype for TSPath {
 
         total_distance
     }
-}
 
-pub fn read_tsp_file(filename: &str) -> Option<Tsp> {
-    TspBuilder::parse_path(filename).ok()
+    /// credit whichever operators made the tour
+    fn feedback(&self, _path: &TSPath, origin: &Origin, parent_fitness: f64, child_fitness: f64, order: &FitnessOrder) {
+        if let Some(operator) = origin.crossover {
+            self.crossovers.reward(operator, parent_fitness, child_fitness, order);
+        }
+        if let Some(operator) = origin.mutation {
+            self.mutations.reward(operator, parent_fitness, child_fitness, order);
+        }
+    }
+
+    /// the crossover pool, then the mutation pool
+    fn save_state(&self) -> Vec<Vec<String>> {
+        vec![self.crossovers.to_fields(), self.mutations.to_fields()]
+    }
+
+    fn load_state(&mut self, records: &[Vec<String>]) -> Result<(), Box<dyn Error>> {
+        let [crossovers, mutations] = records else {
+            return Err(format!("expected the state of 2 operator pools, found {}", records.len()).into());
+        };
+        self.crossovers.load_fields(crossovers)?;
+        self.mutations.load_fields(mutations)
+    }
 }
 
-/// initialise with predetermined dataset and values
-pub fn initialise_with_values(gen: &mut Generation<TSPath>, dataset: Arc<Tsp>, mutation_rate: f64, crossover_rate: f64) {
-    for _ in 0..gen.get_population_size() {
-        gen.push(TSPath::new(dataset.clone(), mutation_rate, crossover_rate));
+impl Genotype for TSPath {
+    /// a tour is the same no matter where it starts or which way round it goes,
+    /// so this is the edge distance, see `Permutation::edge_distance`
+    fn distance(&self, other: &Self) -> f64 {
+        self.path.edge_distance(&other.path)
+    }
+
+    /// the same for every rotation and reversal of a tour, see `Permutation::cyclic_hash`
+    fn canonical_hash(&self) -> u64 {
+        self.path.cyclic_hash()
     }
 }
 
-// --------------------
-// Mutation Operators
-// --------------------
+/// the dataset is shared by every path, so it's left out of the checkpoint,
+/// which stores the path of the dataset file once instead
+impl Checkpoint for TSPath {
+    /// `[mutation rate, crossover rate, cities...]`, the rates being empty unless they are self-adaptive
+    fn to_fields(&self) -> Vec<String> {
+        let mut fields = match self.rates {
+            Some(rates) => vec![rates.mutation.to_string(), rates.crossover.to_string()],
+            None => vec![String::new(), String::new()]
+        };
+        fields.extend(self.path.to_fields());
+        fields
+    }
 
-/// # Partial Shuffle Mutation (PSM)
-/// shuffle a section of the genes in the genotype
-/// this one is far more destructive
-fn partial_shuffle_mutation(parent: &TSPath) -> TSPath {
-    let mut rng = thread_rng();
-    let mut child = (*parent).clone();
+    fn from_fields(fields: &[&str]) -> Result<Self, Box<dyn Error>> {
+        if fields.len() < 2 {
+            return Err("tsp path is missing its rates".into());
+        }
 
-    let i = rng.gen_range(0..parent.length() - 1);
-    let j = rng.gen_range(i..parent.length());
+        let rates = if fields[0].is_empty() {
+            None
+        } else {
+            Some(Rates { mutation: fields[0].parse()?, crossover: fields[1].parse()? })
+        };
 
-    if i != j {
-        let slice = &mut child.path[i..=j];
-        slice.shuffle(&mut rng);
+        Ok(TSPath {
+            path: Permutation::from_fields(&fields[2..])?,
+            rates
+        })
     }
-
-    child
 }
 
-/// # Reverse Sequence Mutation (RSM)
-/// we take a sequence between positions i and j, with i<j
-/// the gene order of this sequence is then reversed
-fn reverse_sequence_mutation(parent: &TSPath) -> TSPath {
-    let mut rng = thread_rng();
-    let mut child = (*parent).clone();
-
-    let i = rng.gen_range(0..parent.length() - 1);
-    let j = rng.gen_range(i..parent.length());
+/// # Tour Costs
+/// a tour's length, traded against its longest leg, the furthest between two consecutive cities,
+/// e.g. the furthest a driver goes without a stop
+/// both are minimised, for finding the pareto front with `Nsga2`
+/// random tours and the operators are the same as for the length alone
+#[derive(Debug, Clone)]
+pub struct TourCosts {
+    tsp: TravellingSalesperson
+}
 
-    if i != j {
-        let slice = &mut child.path[i..=j];
-        slice.reverse();
+impl TourCosts {
+    pub fn new(tsp: TravellingSalesperson) -> Self {
+        TourCosts { tsp }
     }
 
-    child
+    /// the position of each city on the tour, in order
+    fn positions(&self, path: &TSPath) -> Vec<(f64, f64)> {
+        let map = self.tsp.data.node_coords();
+        path.get_path().iter()
+            .map(|city| {
+                let pos = map.get(city).expect("city not found").pos();
+                (pos[0], pos[1])
+            })
+            .collect()
+    }
 }
 
-/// # Swap Mutation (SM)
-/// does what it says on the tin
-fn swap_mutation(parent: &TSPath) -> TSPath {
-    let mut rng = thread_rng();
-    let mut child = (*parent).clone();
+impl Variation for TourCosts {
+    type Genome = TSPath;
 
-    let i = rng.gen_range(0..parent.length());
-    let j = rng.gen_range(0..parent.length());
+    fn random<R: Rng + ?Sized>(&self, rng: &mut R) -> TSPath {
+        self.tsp.random(rng)
+    }
 
-    if i != j {
-        child.path.swap(i, j);
+    fn crossover<R: Rng + ?Sized>(&self, x: &TSPath, y: &TSPath, rng: &mut R) -> (TSPath, TSPath) {
+        self.tsp.crossover(x, y, rng)
     }
 
-    child
+    fn mutation<R: Rng + ?Sized>(&self, path: &TSPath, rng: &mut R) -> TSPath {
+        self.tsp.mutation(path, rng)
+    }
 }
 
-// --------------------
-// Crossover Operators
-// --------------------
-
-/// # Partially Mapped Crossover (PMX)
-/// let parent_0 consisting of genotypes a_0...a_n and parent_1 with genotypes b_0...b_n
-/// let empty genomes child_0 and child_1 of length n
-/// we take two random numbers in the range [0, n], i and j, with i < j
-/// elements a_i...a_j are placed in child_1 in the same locations, and b_i...b_j similarly in child_0
-/// we then create a bijective mapping as follows
-/// f(a_k) = b_k, and f(b_k) = a_k
-/// we copy the remaining elements in parent_0 to child_1, passing them through this mapping, and do the same from parent_1 to child_0, ensuring that we have no invalid tours
-fn partially_mapped_crossover(parent_0: &TSPath, parent_1: &TSPath) -> (TSPath, TSPath) {
-    let mut rng = thread_rng(); 
-    let length = parent_0.length();
-
-    let i = rng.gen_range(0..length - 1);
-    let j = rng.gen_range(i+1..length);
-
-    let mut child_0 = parent_0.clone();
-    let mut child_1 = parent_1.clone();
-
-    let mut mapping = BiMap::new();
-
-    // copy middle sections from parents
-    // and create mapping between elements
-    for idx in i..=j {
-        child_1.path[idx] = parent_0.path[idx];
-        child_0.path[idx] = parent_1.path[idx];
-
-        mapping.insert(parent_0.path[idx], parent_1.path[idx]);
-    }
-
-    // copy left and right sections across, through the mapping
-    for idx in 0..length {
-        if idx < i || idx > j {
-            // parent_0 and child_1
-            // check if the valuee is in the mapping
-            let value = if mapping.contains_left(&parent_0.path[idx]) { 
-                // if so, return the result of the map
-                mapping.get_by_left(&parent_0.path[idx]).unwrap()
-            } else { 
-                // otherwise just return the value
-                &parent_0.path[idx]
-            };
-            child_1.path[idx] = *value;
-
-            // parent_1 and child_0
-            // check if the valuee is in the mapping
-            let value = if mapping.contains_right(&parent_1.path[idx]) { 
-                // if so, return the result of the map
-                mapping.get_by_right(&parent_1.path[idx]).unwrap()
-            } else { 
-                // otherwise just return the value
-                &parent_1.path[idx]
-            };
-            child_0.path[idx] = *value;
-        }
+impl MultiObjective for TourCosts {
+    /// `[length, longest leg]`
+    fn objectives(&self, path: &TSPath) -> Vec<f64> {
+        let positions = self.positions(path);
+        let len = positions.len();
+
+        // the leg leaving each city, wrapping back to the start
+        let longest_leg = (0..len)
+            .map(|c| {
+                let (x_0, y_0) = positions[c];
+                let (x_1, y_1) = positions[(c + 1) % len];
+                (x_1 - x_0).hypot(y_1 - y_0)
+            })
+            .fold(0.0, f64::max);
+
+        vec![self.tsp.fitness(path), longest_leg]
     }
 
-    (child_0, child_1)
+    fn directions(&self) -> Vec<FitnessOrder> {
+        vec![FitnessOrder::Min, FitnessOrder::Min]
+    }
 }
 
-/// # Order Crossover (OX)
-/// Select k random positions from parent_0 and copy them into child_0
-/// iterate through parent_1 and copy each currently unused index into the next empty space in child_0
-/// repeat this process using the n-k positions not chosen from parent_0
-fn order_crossover(parent_0: &TSPath, parent_1: &TSPath) -> (TSPath, TSPath) {
-    let mut rng = thread_rng();
-    let length = parent_0.length();
-    let k = rng.gen_range(0..length);
+pub fn read_tsp_file(filename: &str) -> Option<Tsp> {
+    TspBuilder::parse_path(filename).ok()
+}
 
-    let mut indices = HashSet::new();
+/// without this the population collapses onto copies of the elites
+/// duplicate tours are mutated away, and if it converges anyway,
+/// everything but the elites is replaced with heavily mutated copies of them
+fn maintain_diversity(gen: &mut Generation<TSPath>) {
+    gen.set_duplicate_elimination(Some(Reseed::Mutated { mutations: 1 }));
+    gen.set_restart(Some(Restart {
+        min_diversity: 1.0,
+        elites: 2,
+        reseed: Reseed::Mutated { mutations: 10 }
+    }));
+}
 
-    let mut child_0 = parent_0.clone();
-    let mut child_1 = parent_1.clone();
+/// # Plot Observer
+/// calls the python scripts to plot the best and average fitness, and the best path found,
+/// once `runs` runs have finished, so that a whole grid search is plotted together
+struct PlotObserver {
+    filename: String,
+    runs: usize,
+    finished: usize,
+    best: Option<Individual<TSPath>>
+}
 
-    // set values in child arrays to usize::MAX to represent "empty" indices
-    // it's unlikely that we ever need to represent that many cities, otherwise i'd use an option
-    for idx in 0..length {
-        child_0.path[idx] = usize::MAX;
-        child_1.path[idx] = usize::MAX;
+impl PlotObserver {
+    fn new(filename: &str, runs: usize) -> Self {
+        PlotObserver {
+            filename: filename.to_string(),
+            runs,
+            finished: 0,
+            best: None
+        }
     }
 
-    // generate k random positions
-    while indices.len() < k {
-        indices.insert(rng.gen_range(0..length));
+    fn plot(&self) -> Result<(), Box<dyn Error>> {
+        let filename = &self.filename;
+        let best_path = self.best.as_ref().map(|best| best.genome().get_path().to_vec()).unwrap_or_default();
+
+        // python environment
+        let python_path = ".venv/bin/python3";
+        let plotting_script = "plotting/plot_tsp_fitness.py";
+        let path_script = "plotting/plot_tsp_path.py";
+
+        // plot graph
+        // best fitness
+        let best_output = Command::new(python_path)
+            .arg(plotting_script)
+            .arg(format!("output/{}", filename))
+            .arg("best_fitness")
+            .output()?;
+
+        if !best_output.status.success() {
+            eprintln!("error: {}", String::from_utf8_lossy(&best_output.stderr));
+        }
+
+        // average fitness
+        let average_output = Command::new(python_path)
+            .arg(plotting_script)
+            .arg(format!("output/{}", filename))
+            .arg("average_fitness")
+            .output()?;
+
+        if !average_output.status.success() {
+            eprintln!("error: {}", String::from_utf8_lossy(&average_output.stderr));
+        }
+
+        // plot path
+        let path_output = Command::new(python_path)
+            .arg(path_script)
+            .arg(format!("datasets/{}", filename))
+            .arg(format!("{:?}", best_path))
+            .output()?;
+
+        if !path_output.status.success() {
+            eprintln!("error: {}", String::from_utf8_lossy(&path_output.stderr));
+        }
+
+        Ok(())
     }
+}
 
-    // place the k selected values into child_0, and the other n-k values into child_1
-    for idx in 0..length {
-        if indices.contains(&idx) {
-            child_0.path[idx] = parent_0.path[idx];
-        } else {
-            child_1.path[idx] = parent_0.path[idx];
+impl Observer<TSPath> for PlotObserver {
+    fn on_termination(&mut self, _generation: &Generation<TSPath>, summary: &RunSummary<TSPath>) -> Result<(), Box<dyn Error>> {
+        // see if this is the best solution found for the dataset
+        if self.best.as_ref().is_none_or(|best| summary.best.fitness() < best.fitness()) {
+            self.best = Some(summary.best.clone());
+        }
+
+        self.finished += 1;
+        if self.finished == self.runs {
+            self.plot()?;
         }
+
+        Ok(())
     }
+}
+
+/// # Rate Observer
+/// writes the mean and standard deviation of the population's self-adaptive rates every generation,
+/// so self-adaptive rates can be watched as they evolve, then plots them once the run has finished
+struct RateObserver {
+    writer: Writer<BufWriter<File>>,
+    output_path: String
+}
+
+impl RateObserver {
+    const HEADERS: [&'static str; 5] = ["epoch", "mean_mutation_rate", "std_mutation_rate", "mean_crossover_rate", "std_crossover_rate"];
 
-    // fill out the empty spaces
-    let mut child_0_idx = 0;
-    let mut child_1_idx = 0;
-    for idx in 0..length {
-        // insert the next unused index
-        if !child_0.path.contains(&parent_1.path[idx]) {
-            // find the next empty space
-            while child_0.path[child_0_idx] != usize::MAX {
-                child_0_idx += 1;
+    fn new(output_path: &str) -> Result<Self, Box<dyn Error>> {
+        let mut writer = Writer::from_writer(BufWriter::new(File::create(output_path)?));
+        writer.write_record(Self::HEADERS)?;
+
+        Ok(RateObserver {
+            writer,
+            output_path: output_path.to_string()
+        })
+    }
+
+    /// carry on writing to the file of a run resumed from a checkpoint
+    /// rows after the checkpoint's generation may have been written before the run stopped, so they're dropped,
+    /// as is a last row cut short by the run stopping part way through writing it
+    fn resume(output_path: &str, generation: usize) -> Result<Self, Box<dyn Error>> {
+        let mut reader = ReaderBuilder::new().flexible(true).from_path(output_path)?;
+
+        let mut kept = Vec::new();
+        for record in reader.records() {
+            let record = record?;
+            let epoch = record.get(0).and_then(|epoch| epoch.parse::<usize>().ok());
+            if record.len() == Self::HEADERS.len() && epoch.is_some_and(|epoch| epoch <= generation) {
+                kept.push(record);
             }
-            child_0.path[child_0_idx] = parent_1.path[idx];
-            child_0_idx += 1;
         }
-        // repeat for child_1
-        // this could possibly be an else instead of another if but i'm not 100% sure
-        if !child_1.path.contains(&parent_1.path[idx]) {
-            // find the next empty space
-            while child_1.path[child_1_idx] != usize::MAX {
-                child_1_idx += 1;
-            }
-            child_1.path[child_1_idx] = parent_1.path[idx];
-            child_1_idx += 1;
+
+        let mut observer = RateObserver::new(output_path)?;
+        for record in kept {
+            observer.writer.write_record(&record)?;
+        }
+        Ok(observer)
+    }
+}
+
+/// mean and standard deviation
+fn moments(values: impl Iterator<Item = f64> + Clone) -> (f64, f64) {
+    let len = values.clone().count() as f64;
+    let mean = values.clone().sum::<f64>() / len;
+    let variance = values.map(|v| (v - mean).powi(2)).sum::<f64>() / len;
+    (mean, variance.sqrt())
+}
+
+impl Observer<TSPath> for RateObserver {
+    fn on_generation(&mut self, generation: &Generation<TSPath>, _order: &FitnessOrder) -> Result<(), Box<dyn Error>> {
+        let rates = generation.population.iter().filter_map(|individual| individual.genome().rates());
+        let (mean_mutation, std_mutation) = moments(rates.clone().map(|rates| rates.mutation));
+        let (mean_crossover, std_crossover) = moments(rates.map(|rates| rates.crossover));
+
+        self.writer.write_record([
+            generation.get_generation().to_string(),
+            mean_mutation.to_string(),
+            std_mutation.to_string(),
+            mean_crossover.to_string(),
+            std_crossover.to_string()
+        ])?;
+
+        // flushed every generation, so the file is complete up to any checkpoint
+        self.writer.flush()?;
+        Ok(())
+    }
+
+    fn on_termination(&mut self, _generation: &Generation<TSPath>, _summary: &RunSummary<TSPath>) -> Result<(), Box<dyn Error>> {
+        self.writer.flush()?;
+
+        let output = Command::new(".venv/bin/python3")
+            .arg("plotting/plot_tsp_rates.py")
+            .arg(&self.output_path)
+            .output()?;
+
+        if !output.status.success() {
+            eprintln!("error: {}", String::from_utf8_lossy(&output.stderr));
         }
+
+        Ok(())
     }
+}
 
-    (child_0, child_1)
+/// standard deviation of the log-normal perturbation of self-adaptive rates
+const LEARNING_RATE: f64 = 0.2;
+/// how the crossover and mutation operators are picked, `Adaptation::Fixed` keeps the hard-coded weights
+const OPERATOR_ADAPTATION: Adaptation = Adaptation::Fixed;
+
+/// eliminate duplicate tours and restart converged populations, see `maintain_diversity`
+/// off by default, so the grid search runs the plain algorithm
+const MAINTAIN_DIVERSITY: bool = false;
+
+/// how many of the best distinct tours are kept for each dataset
+const HALL_OF_FAME_SIZE: usize = 10;
+
+/// write the hall of fame to `output/<dataset>_hall_of_fame.csv`
+fn write_hall_of_fame(filename: &str, hall_of_fame: &HallOfFame<TSPath>) -> Result<(), Box<dyn Error>> {
+    let name = filename.strip_suffix(".tsp").unwrap_or(filename);
+    let file = File::create(format!("output/{}_hall_of_fame.csv", name))?;
+    hall_of_fame.write(BufWriter::new(file))
+}
+
+/// how often each operator was used, and how often it made a child better than its parents
+fn print_operator_statistics(problem: &TravellingSalesperson) {
+    for (kind, pool) in [("crossover", problem.crossovers()), ("mutation", problem.mutations())] {
+        for operator in pool.statistics() {
+            println!("{} {}: chosen {} times, {} of {} children improved, probability {:.3}", kind, operator.name, operator.chosen, operator.successes, operator.rewarded, operator.probability);
+        }
+    }
 }
 
 /// anaylse the dataset and output the result to a csv file
 /// then, call the python script to plot the average fitness
-pub fn analyse_dataset(filepath: &str) -> Result<(), Box<dyn Error>> {
+/// the whole grid search is driven from one rng, so the same seed gives the same csv
+/// the best distinct tours over every run of the grid search are written to a hall of fame csv
+pub fn analyse_dataset(filepath: &str, seed: u64) -> Result<(), Box<dyn Error>> {
     let dataset = read_tsp_file(filepath).expect("no file found");
     let dataset_arc = Arc::new(dataset);
 
@@ -336,114 +649,225 @@ pub fn analyse_dataset(filepath: &str) -> Result<(), Box<dyn Error>> {
     // set up buffered writer
     let file = File::create(&output_path)?;
     let buf_writer = BufWriter::new(file);  // Wrap in a BufWriter
-    let mut writer = Writer::from_writer(buf_writer);
 
     // [IDENTIFIER, IDENTIFIER_ALTERNATIVE, X, Y, Y_alternative]
-    writer.write_record(["crossover_rate", "mutation_rate", "epoch", "best_fitness", "average_fitness"])?;
-
-    let order = FitnessOrder::Min;
-
-    let mut overall_best_path = Vec::new();
-    let mut overall_best_fitness: f64 = f64::MAX;
-  
-    let start = Instant::now();
+    let mut csv = CsvObserver::new(buf_writer, &["crossover_rate", "mutation_rate"])?;
 
     // grid search, bounded thanks to manual testing
     // searches crossover rate .8 to 1.0, and mutation rate 0.05 to 0.1
-    for c_step in (80..=100).step_by(10) {
-        for m_step in (5..=10).step_by(1) {
-            let crossover_rate = f64::from(c_step) * 0.01;
-            let mutation_rate = f64::from(m_step) * 0.01;
-
-            let mut generations: usize = 0;
-            let mut lowest_found = f64::MAX;
-            let mut lowest_average = f64::MAX;
-            let mut best_found = Vec::new();
-
-            let mut city: Generation<TSPath> = Generation::new(200);
-            initialise_with_values(&mut city, dataset_arc.clone(), mutation_rate, crossover_rate);
-            
-            let mut gen_since_improvement: usize = 0;
-
-            // check for convergence, and also cap it because i'm on a laptop
-            while gen_since_improvement < 400 && generations < 5000 {
-                epoch(&mut city, &order);
-                generations += 1;
-                gen_since_improvement += 1;
+    let crossover_steps: Vec<u32> = (80..=100).step_by(10).collect();
+    let mutation_steps: Vec<u32> = (5..=10).step_by(1).collect();
+    let mut plot = PlotObserver::new(filename, crossover_steps.len() * mutation_steps.len());
 
-                // check if we have a new best solution, or if the average has improved
-                // either of these means we're improving
-                if city.get_best_fitness(&order) < lowest_found {
-                    lowest_found = city.get_best_fitness(&order);
-                    best_found = (*city.get_best_solution(&order).get_path()).clone();
+    let order = FitnessOrder::Min;
+    let mut rng = StdRng::seed_from_u64(seed);
+    let selection = Tournament::new(2);
+    let replacement = Replacement::Generational { elites: 2 };
+
+    // check for convergence, and also cap it because i'm on a laptop
+    let termination = Termination {
+        max_generations: Some(5000),
+        stagnation: Some(400),
+        ..Default::default()
+    };
+
+    let mut hall_of_fame = HallOfFame::new(HALL_OF_FAME_SIZE);
+  
+    let start = Instant::now();
 
-                    // see if this is the best solution found for the dataset
-                    if lowest_found < overall_best_fitness {
-                        overall_best_fitness = lowest_found;
-                        overall_best_path = best_found.clone();
-                    }
+    for c_step in &crossover_steps {
+        for m_step in &mutation_steps {
+            let crossover_rate = f64::from(*c_step) * 0.01;
+            let mutation_rate = f64::from(*m_step) * 0.01;
+
+            let problem = TravellingSalesperson::new(dataset_arc.clone(), mutation_rate, crossover_rate)
+                .with_operator_adaptation(OPERATOR_ADAPTATION);
+            let mut city = Generation::new(200);
+            initialise(&mut city, &problem, &mut rng);
+            if MAINTAIN_DIVERSITY {
+                maintain_diversity(&mut city);
+            }
 
-                    gen_since_improvement = 0;
-                }
+            csv.set_prefix(vec![crossover_rate.to_string(), mutation_rate.to_string()]);
 
-                if city.get_average_fitness() < lowest_average {
-                    lowest_average = city.get_average_fitness();
+            let mut engine = Engine::new(problem, city, order, selection.clone(), Model::Generational(replacement.clone()), termination.clone());
+            engine.set_hall_of_fame(HALL_OF_FAME_SIZE);
+            let summary = engine.run(&mut rng, &mut [&mut csv, &mut plot])?;
 
-                    gen_since_improvement = 0;
-                }
+            let lowest_found = summary.best.fitness();
+            let best_found = summary.best.genome().get_path().to_vec();
 
-                // write generation to csv file
-                writer.write_record([crossover_rate.to_string(), mutation_rate.to_string(), generations.to_string(), city.get_best_fitness(&order).to_string(), city.get_average_fitness().to_string()])?;
+            // keep the best tours found for the dataset
+            if let Some(run_hall_of_fame) = engine.hall_of_fame() {
+                hall_of_fame.merge(run_hall_of_fame, &order);
             }
-            writer.flush()?;
 
-            println!("dataset: {} with crossover rate: {} and mutation rate: {}\nbest fitness: {}\nbest solution: {:?}", filename, crossover_rate, mutation_rate, lowest_found, best_found);
+            println!("dataset: {} with crossover rate: {} and mutation rate: {}\nstopped by: {:?} after {} generations, {} restarts\nbest fitness: {}\nbest solution: {:?}", filename, crossover_rate, mutation_rate, summary.reason, summary.generations, engine.generation().get_restarts(), lowest_found, best_found);
+            print_operator_statistics(engine.problem());
         }
     }
 
     let elapsed = start.elapsed();
     println!("time taken for {}: {:.2?}", filename, elapsed);
 
-    println!("best path found for dataset {}:\n{:?}\nfitness: {}", filename, overall_best_path, overall_best_fitness);
+    if let Some(best) = hall_of_fame.best() {
+        println!("best path found for dataset {}:\n{:?}\nfitness: {}", filename, best.individual().genome().get_path(), best.fitness());
+    }
+    write_hall_of_fame(filename, &hall_of_fame)?;
+
+    Ok(())
+}
+
+/// a single run with self-adaptive rates, instead of the grid search
+/// the fitness csv has the same columns as `analyse_dataset`, with the rates written as "self-adaptive",
+/// and the rates themselves are written to a separate csv every generation
+///
+/// a checkpoint is saved every 100 generations, and the run resumes from it if it's there when this is called
+/// the checkpoint is deleted once the run finishes, and the hall of fame is written out
+/// the operator pools are checkpointed along with the run, so their statistics cover all of it
+pub fn analyse_dataset_self_adaptive(filepath: &str, seed: u64) -> Result<(), Box<dyn Error>> {
+    let filename = filepath.strip_prefix("./datasets/").unwrap();
+    let name = filename.strip_suffix(".tsp").unwrap_or(filename);
+    let rates_path = format!("output/{}_rates.csv", name);
+
+    let checkpointing = Checkpointing {
+        path: PathBuf::from(format!("output/{}.checkpoint", name)),
+        interval: 100,
+        reference: filepath.to_string()
+    };
+
+    let order = FitnessOrder::Min;
+    let selection = Tournament::new(2);
+    let model = Model::Generational(Replacement::Generational { elites: 2 });
+
+    let termination = Termination {
+        max_generations: Some(5000),
+        stagnation: Some(400),
+        ..Default::default()
+    };
+
+    let (mut engine, mut rng, mut rates) = if checkpointing.path.exists() {
+        let (engine, rng) = Engine::resume(&checkpointing.path, order, selection, model, termination, |reference| {
+            let dataset = read_tsp_file(reference).ok_or("no file found")?;
+            Ok(TravellingSalesperson::self_adaptive(Arc::new(dataset), LEARNING_RATE).with_operator_adaptation(OPERATOR_ADAPTATION))
+        })?;
+        println!("dataset: {} resumed from generation {}", filename, engine.generation().get_generation());
+
+        let rates = RateObserver::resume(&rates_path, engine.generation().get_generation())?;
+        (engine, rng, rates)
+    } else {
+        let dataset = read_tsp_file(filepath).expect("no file found");
+        let problem = TravellingSalesperson::self_adaptive(Arc::new(dataset), LEARNING_RATE).with_operator_adaptation(OPERATOR_ADAPTATION);
+        let mut rng = CheckpointRng::seed_from_u64(seed);
+
+        let mut city = Generation::new(200);
+        initialise(&mut city, &problem, &mut rng);
+        if MAINTAIN_DIVERSITY {
+            maintain_diversity(&mut city);
+        }
+
+        let mut engine = Engine::new(problem, city, order, selection, model, termination);
+        engine.set_hall_of_fame(HALL_OF_FAME_SIZE);
+        (engine, rng, RateObserver::new(&rates_path)?)
+    };
 
-    // python environment
-    let python_path = ".venv/bin/python3";
-    let plotting_script = "plotting/plot_tsp_fitness.py";
-    let path_script = "plotting/plot_tsp_path.py";
+    // written from scratch even when resuming, the generation's history fills in the earlier rows
+    let file = File::create(format!("output/{}", filename))?;
+    let mut csv = CsvObserver::new(BufWriter::new(file), &["crossover_rate", "mutation_rate"])?;
+    csv.set_prefix(vec!["self-adaptive".to_string(), "self-adaptive".to_string()]);
 
-    // plot graph
-    // best fitness
-    let best_output = Command::new(python_path)
-        .arg(plotting_script)
-        .arg(format!("output/{}", filename))
-        .arg("best_fitness")
-        .output()?;
+    let mut plot = PlotObserver::new(filename, 1);
 
-    if !best_output.status.success() {
-        eprintln!("error: {}", String::from_utf8_lossy(&best_output.stderr));
+    let summary = engine.run_with_checkpoints(&mut rng, &mut [&mut csv, &mut rates, &mut plot], &checkpointing)?;
+    fs::remove_file(&checkpointing.path)?;
+
+    if let Some(hall_of_fame) = engine.hall_of_fame() {
+        write_hall_of_fame(filename, hall_of_fame)?;
     }
 
-    // average fitness
-    let average_output = Command::new(python_path)
-        .arg(plotting_script)
-        .arg(format!("output/{}", filename))
-        .arg("average_fitness")
-        .output()?;
+    let final_rates = engine.problem().rates(summary.best.genome());
+    println!("dataset: {} with self-adaptive rates\nstopped by: {:?} after {} generations ({:.2?}), {} restarts\nbest fitness: {}\nfinal rates: crossover {}, mutation {}\nbest solution: {:?}", filename, summary.reason, summary.generations, summary.elapsed, engine.generation().get_restarts(), summary.best.fitness(), final_rates.crossover, final_rates.mutation, summary.best.genome().get_path());
+    print_operator_statistics(engine.problem());
+
+    Ok(())
+}
+
+/// islands in the island model, each with a population of `ISLAND_SIZE`
+const ISLANDS: usize = 4;
+const ISLAND_SIZE: usize = 50;
+
+/// a single run of the island model, with the same total population as the grid search,
+/// and rates from the middle of it
+/// the islands exchange their best tours around a ring, see `Migration`,
+/// and the run stops once neither the best nor the average over all the islands has improved in a while
+pub fn analyse_dataset_islands(filepath: &str, seed: u64) -> Result<(), Box<dyn Error>> {
+    let dataset = read_tsp_file(filepath).expect("no file found");
+    let filename = filepath.strip_prefix("./datasets/").unwrap();
 
-    if !average_output.status.success() {
-        eprintln!("error: {}", String::from_utf8_lossy(&average_output.stderr));
+    let problem = TravellingSalesperson::new(
+        Arc::new(dataset),
+        (INITIAL_MUTATION_RATES.0 + INITIAL_MUTATION_RATES.1) / 2.0,
+        (INITIAL_CROSSOVER_RATES.0 + INITIAL_CROSSOVER_RATES.1) / 2.0
+    ).with_operator_adaptation(OPERATOR_ADAPTATION);
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    let islands: Vec<Generation<TSPath>> = (0..ISLANDS)
+        .map(|_| {
+            let mut city = Generation::new(ISLAND_SIZE);
+            initialise(&mut city, &problem, &mut rng);
+            city
+        })
+        .collect();
+
+    let termination = Termination {
+        max_generations: Some(5000),
+        stagnation: Some(400),
+        ..Default::default()
+    };
+    let model = Model::Generational(Replacement::Generational { elites: 2 });
+
+    let mut island_model = IslandModel::new(problem, islands, FitnessOrder::Min, Tournament::new(2), model, termination, Migration::default(), &mut rng);
+    let summary = island_model.run(&mut rng);
+
+    println!("dataset: {} on {} islands\nstopped by: {:?} after {} generations ({:.2?}), {} evaluations\nbest fitness: {}\nbest solution: {:?}", filename, ISLANDS, summary.reason, summary.generations, summary.elapsed, summary.evaluations, summary.best.fitness(), summary.best.genome().get_path());
+    for (idx, island) in island_model.islands().enumerate() {
+        println!("island {}: best fitness {}", idx, island.get_statistics().best);
     }
 
-    // plot path
-    let path_output = Command::new(python_path)
-        .arg(path_script)
-        .arg(format!("datasets/{}", filename))
-        .arg(format!("{:?}", overall_best_path))
-        .output()?;
+    Ok(())
+}
+
+const PARETO_GENERATIONS: usize = 2000;
+
+/// a single run of NSGA-II, trading the length of each tour against its longest leg, see `TourCosts`,
+/// with the same population size as the grid search, and rates from the middle of it
+/// the non-dominated tours are written to `output/<dataset>_pareto.csv`
+pub fn analyse_dataset_pareto(filepath: &str, seed: u64) -> Result<(), Box<dyn Error>> {
+    let dataset = read_tsp_file(filepath).expect("no file found");
+    let filename = filepath.strip_prefix("./datasets/").unwrap();
+    let name = filename.strip_suffix(".tsp").unwrap_or(filename);
 
-    if !path_output.status.success() {
-        eprintln!("error: {}", String::from_utf8_lossy(&path_output.stderr));
+    let tsp = TravellingSalesperson::new(
+        Arc::new(dataset),
+        (INITIAL_MUTATION_RATES.0 + INITIAL_MUTATION_RATES.1) / 2.0,
+        (INITIAL_CROSSOVER_RATES.0 + INITIAL_CROSSOVER_RATES.1) / 2.0
+    );
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    let mut nsga2 = Nsga2::new(TourCosts::new(tsp), 200);
+    nsga2.initialise(&mut rng);
+
+    let start = Instant::now();
+    for _ in 0..PARETO_GENERATIONS {
+        nsga2.epoch(&mut rng);
     }
 
+    let file = File::create(format!("output/{}_pareto.csv", name))?;
+    nsga2.write_pareto_front(BufWriter::new(file))?;
+
+    let front = nsga2.pareto_front();
+    let shortest = front.iter().map(|individual| individual.objectives()[0]).fold(f64::INFINITY, f64::min);
+    println!("dataset: {} trading length against the longest leg\n{} generations ({:.2?}), {} tours on the pareto front\nshortest tour on it: {}", filename, nsga2.get_generation(), start.elapsed(), front.len(), shortest);
+
     Ok(())
 }